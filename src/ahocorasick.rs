@@ -0,0 +1,231 @@
+// Aho–Corasick automaton for patterns that are a finite set of literal strings, such as keyword
+// alternations, matched in one pass over the string instead of through sets of positions
+
+use std::collections::VecDeque;
+use std::ops::Deref;
+use std::rc::Rc;
+
+use crate::classes::{partition, representatives};
+use crate::literals::{cross, Strings, MAX_CLASS};
+use crate::Regex;
+use crate::Regex::{Eps, Letter, CharClass, Or, Concat};
+
+// Largest literal set turned into an automaton
+pub const MAX_KEYWORDS: usize = 10000;
+
+// The strings an expression matches when they are finitely many literals: made of letters,
+// small classes, alternations and concatenations only. Sets that would grow past MAX_KEYWORDS
+// are given up on before they are built
+pub fn literal_set(regexp: &Rc<Regex>) -> Option<Strings> {
+    match regexp.deref() {
+        Eps() => Some([Vec::new()].into_iter().collect()),
+        Letter(a) => Some([vec![*a]].into_iter().collect()),
+        CharClass(charvec) if charvec.iter().filter(|x| **x).count() <= MAX_CLASS => {
+            Some((0..256).filter(|x| charvec[*x]).map(|x| vec![x as u8]).collect())
+        },
+        Or(r1, r2) => {
+            let x = literal_set(r1)?;
+            let y = literal_set(r2)?;
+            if x.len() + y.len() > MAX_KEYWORDS {
+                return None;
+            }
+            Some(x.union(&y).cloned().collect())
+        },
+        Concat(r1, r2) => {
+            let x = literal_set(r1)?;
+            let y = literal_set(r2)?;
+            if x.len() * y.len() > MAX_KEYWORDS {
+                return None;
+            }
+            Some(cross(&x, &y))
+        },
+        _ => None
+    }
+}
+
+// The trie of the keywords completed with the failure transitions into a DFA; the trie edge
+// out of a state is the transition going one byte deeper. Every byte of a keyword has a class
+// of its own and all the other bytes share one, with one transition per class
+pub struct AhoCorasick {
+    // Class of every byte
+    classes: Vec<u8>,
+    // trans[state][class]
+    trans: Vec<Vec<usize>>,
+    depth: Vec<usize>,
+    // Whether the state spells a whole keyword
+    keyword: Vec<bool>,
+    // Nearest state down the failure links (the state itself included) spelling a keyword
+    output: Vec<Option<usize>>,
+    fail: Vec<usize>
+}
+
+impl AhoCorasick {
+    pub fn new(keywords: &Strings) -> AhoCorasick {
+        let mut used: Vec<bool> = vec![false; 256];
+        for byte in keywords.iter().flatten() {
+            used[*byte as usize] = true;
+        }
+        let bytes: Vec<Vec<bool>> = (0..256).filter(|x| used[*x]).map(|x| (0..256).map(|y| y == x).collect()).collect();
+        let classes = partition(&bytes);
+        let width = representatives(&classes).len();
+        // Trie, with usize::MAX for missing edges
+        let mut trans: Vec<Vec<usize>> = vec![vec![usize::MAX; width]];
+        let mut depth: Vec<usize> = vec![0];
+        let mut keyword: Vec<bool> = vec![false];
+        for w in keywords {
+            let mut s = 0;
+            for byte in w {
+                let c = classes[*byte as usize] as usize;
+                if trans[s][c] == usize::MAX {
+                    trans.push(vec![usize::MAX; width]);
+                    depth.push(depth[s] + 1);
+                    keyword.push(false);
+                    trans[s][c] = trans.len() - 1;
+                }
+                s = trans[s][c];
+            }
+            keyword[s] = true;
+        }
+        // Failure links breadth first, filling in the missing edges from the failure state's
+        let n = trans.len();
+        let mut fail: Vec<usize> = vec![0; n];
+        let mut output: Vec<Option<usize>> = vec![None; n];
+        let mut queue: VecDeque<usize> = VecDeque::new();
+        if keyword[0] {
+            output[0] = Some(0);
+        }
+        for t in trans[0].iter_mut() {
+            match *t {
+                usize::MAX => *t = 0,
+                next => queue.push_back(next)
+            }
+        }
+        while let Some(s) = queue.pop_front() {
+            output[s] = if keyword[s] { Some(s) } else { output[fail[s]] };
+            let from_fail = trans[fail[s]].clone();
+            for (t, f) in trans[s].iter_mut().zip(from_fail) {
+                match *t {
+                    usize::MAX => *t = f,
+                    next => {
+                        fail[next] = if s == 0 { 0 } else { f };
+                        queue.push_back(next);
+                    }
+                }
+            }
+        }
+        AhoCorasick { classes, trans, depth, keyword, output, fail }
+    }
+
+    // Transition on a byte, through its class
+    fn next(&self, state: usize, byte: u8) -> usize {
+        self.trans[state][self.classes[byte as usize] as usize]
+    }
+
+    // Checks whether the whole string is a keyword, following trie edges only
+    pub fn is_match(&self, s: &[u8]) -> bool {
+        let mut curr = 0;
+        for byte in s {
+            let next = self.next(curr, *byte);
+            if self.depth[next] != self.depth[curr] + 1 {
+                return false;
+            }
+            curr = next;
+        }
+        self.keyword[curr]
+    }
+
+    // Leftmost longest occurrence of a keyword. The scan goes on after a first occurrence until
+    // the current state can no longer spell one starting at or before it
+    pub fn find(&self, s: &[u8]) -> Option<(usize, usize)> {
+        let mut best: Option<(usize, usize)> = if self.keyword[0] { Some((0, 0)) } else { None };
+        let mut curr = 0;
+        for (i, byte) in s.iter().enumerate() {
+            curr = self.next(curr, *byte);
+            let end = i + 1;
+            let mut out = self.output[curr];
+            while let Some(k) = out {
+                let start = end - self.depth[k];
+                best = match best {
+                    Some((b, _)) if b < start => best,
+                    _ => Some((start, end))
+                };
+                out = if k == 0 { None } else { self.output[self.fail[k]] };
+            }
+            if let Some((b, _)) = best {
+                if end - self.depth[curr] > b {
+                    break;
+                }
+            }
+        }
+        best
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::all_strings;
+    use crate::{compile, parse};
+
+    const PATTERNS: [&str; 9] = [
+        "abc|b", "a|ab|abc", "(a|b)(a|c)", "bca|c|cab", "[ab]c|cc", "ab(a|())", "aaa|aa", "(ab|ba)(ab|ba)", "abcabc|bcab",
+    ];
+
+    fn keywords(pattern: &str) -> Strings {
+        literal_set(&parse(pattern).unwrap().0).unwrap()
+    }
+
+    // Leftmost longest match found by checking every (start, end) pair
+    fn brute_find(keywords: &Strings, s: &[u8]) -> Option<(usize, usize)> {
+        (0..=s.len()).find_map(|i| (i..=s.len()).rev().find(|j| keywords.contains(&s[i..*j])).map(|j| (i, j)))
+    }
+
+    // Sets too large for an automaton are given up on before they are built
+    #[test]
+    fn large_sets_are_not_built() {
+        let start = std::time::Instant::now();
+        assert!(literal_set(&parse("[a-h]{4}[a-h]{4}").unwrap().0).is_none());
+        assert!(literal_set(&parse("[a-h]{4}|[a-h]{4}b|[a-h]{4}c").unwrap().0).is_none());
+        assert!(start.elapsed().as_millis() < 1000);
+        assert_eq!(keywords("[a-h]{4}").len(), 4096);
+    }
+
+    // Bytes that appear in no keyword share one class
+    #[test]
+    fn one_class_per_keyword_byte() {
+        let ac = AhoCorasick::new(&keywords("abc|ca"));
+        assert_eq!(ac.trans[0].len(), 4);
+        assert_eq!(ac.next(0, b'x'), ac.next(0, 0));
+    }
+
+    #[test]
+    fn literal_sets() {
+        assert_eq!(keywords("a(b|c)").len(), 2);
+        assert!(literal_set(&parse("ab*").unwrap().0).is_none());
+        assert!(literal_set(&parse("a.").unwrap().0).is_none());
+        let strings = all_strings(b"abc", 6);
+        for pattern in PATTERNS {
+            let k = keywords(pattern);
+            let g = compile(pattern).unwrap();
+            for s in &strings {
+                assert_eq!(k.contains(s), g.accepts(s), "{} on {:?}", pattern, s);
+            }
+        }
+    }
+
+    // Whole matches and leftmost longest occurrences against the Glushkov automaton and brute force
+    #[test]
+    fn agrees_with_glushkov() {
+        let strings = all_strings(b"abc", 6);
+        for pattern in PATTERNS {
+            let k = keywords(pattern);
+            let ac = AhoCorasick::new(&k);
+            let g = compile(pattern).unwrap();
+            for s in &strings {
+                assert_eq!(ac.is_match(s), g.accepts(s), "{} on {:?}", pattern, s);
+                assert_eq!(ac.find(s), brute_find(&k, s), "{} on {:?}", pattern, s);
+                assert_eq!(ac.find(s), g.find(s), "{} on {:?}", pattern, s);
+            }
+        }
+    }
+}
@@ -0,0 +1,115 @@
+// Ambiguity of a pattern: a string read along two different accepting paths of the Glushkov
+// automaton, which makes the submatches a capturing engine reports depend on its choices
+
+use crate::redos::{path_bytes, shortest_path};
+use crate::Glushkov;
+
+// A string with two different accepting paths, given as the positions entered
+pub struct Ambiguity {
+    pub witness: Vec<u8>,
+    pub first: Vec<u8>,
+    pub second: Vec<u8>
+}
+
+impl Glushkov {
+    // Shortest string with two accepting paths, searched in the product of the automaton with
+    // itself from (0, 0) through a pair of different positions to a pair of accepting states
+    pub fn ambiguity(&self) -> Option<Ambiguity> {
+        let next = |(a, b, diverged): &(u8, u8, bool)| -> Vec<(u8, (u8, u8, bool))> {
+            self.pair_next(&(*a, *b)).into_iter()
+                .map(|(byte, (j, k))| (byte, (j, k, *diverged || j != k)))
+                .collect()
+        };
+        let path = shortest_path((0, 0, false), next, |(a, b, diverged)| {
+            *diverged && self.is_final(*a) && self.is_final(*b)
+        })?;
+        Some(Ambiguity {
+            witness: path_bytes(&path),
+            first: path.iter().map(|(_, (a, _, _))| *a).collect(),
+            second: path.iter().map(|(_, (_, b, _))| *b).collect()
+        })
+    }
+
+    pub fn is_ambiguous(&self) -> bool {
+        self.ambiguity().is_some()
+    }
+
+    // Renders a path as the positions it enters with their labels
+    pub fn describe_path(&self, path: &[u8]) -> String {
+        let mut res = self.label(0);
+        for x in path {
+            res.push_str(&format!(" -> {} ({})", x, self.label(*x)));
+        }
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::all_strings;
+    use crate::{compile_unsimplified, Glushkov};
+
+    // Number of accepting runs of the automaton on a string
+    fn accepting_runs(g: &Glushkov, s: &[u8]) -> u64 {
+        let n = g.no_of_states as usize;
+        let mut count: Vec<u64> = vec![0; n];
+        count[0] = 1;
+        for byte in s {
+            let mut next: Vec<u64> = vec![0; n];
+            for (i, c) in count.iter().enumerate() {
+                for j in &g.nfa[i] {
+                    if g.state_letter[(j-1) as usize][*byte as usize] {
+                        next[*j as usize] += *c;
+                    }
+                }
+            }
+            count = next;
+        }
+        (0..n).filter(|x| g.is_final(*x as u8)).map(|x| count[x]).sum()
+    }
+
+    // A path enters each position on the byte of the witness it is paired with, and ends accepting
+    fn is_accepting_path(g: &Glushkov, witness: &[u8], path: &[u8]) -> bool {
+        let mut curr = 0u8;
+        for (byte, x) in witness.iter().zip(path) {
+            if !g.nfa[curr as usize].contains(x) || !g.state_letter[(x-1) as usize][*byte as usize] {
+                return false;
+            }
+            curr = *x;
+        }
+        path.len() == witness.len() && g.is_final(curr)
+    }
+
+    #[test]
+    fn shortest_witnesses() {
+        let strings = all_strings(b"abc", 6);
+        for pattern in ["(a|a)*", "a*a*", "(a|ab)(c|bc)", "(ab|a)(ba|b)*c", "a?[ab]?", "(a|ab)(ba|a)"] {
+            let g = compile_unsimplified(pattern).unwrap();
+            let a = g.ambiguity().unwrap_or_else(|| panic!("{}", pattern));
+            assert_ne!(a.first, a.second, "{}", pattern);
+            assert!(is_accepting_path(&g, &a.witness, &a.first), "{}", pattern);
+            assert!(is_accepting_path(&g, &a.witness, &a.second), "{}", pattern);
+            assert!(accepting_runs(&g, &a.witness) >= 2, "{}", pattern);
+            assert!(strings.iter().all(|s| s.len() >= a.witness.len() || accepting_runs(&g, s) < 2), "{}", pattern);
+        }
+    }
+
+    #[test]
+    fn unambiguous() {
+        let strings = all_strings(b"abc", 6);
+        for pattern in ["a*b*", "(a|b)*abb", "(ab|ba)*", "a?b", "(a|bc)*c"] {
+            let g = compile_unsimplified(pattern).unwrap();
+            assert!(!g.is_ambiguous(), "{}", pattern);
+            assert!(strings.iter().all(|s| accepting_runs(&g, s) < 2), "{}", pattern);
+        }
+    }
+
+    #[test]
+    fn paths_are_described() {
+        let g = compile_unsimplified("a|a").unwrap();
+        let a = g.ambiguity().unwrap();
+        assert_eq!(a.witness, b"a");
+        assert_eq!(g.describe_path(&a.first), "start -> 1 (a)");
+        assert_eq!(g.describe_path(&a.second), "start -> 2 (a)");
+    }
+}
@@ -0,0 +1,407 @@
+// Common automaton type shared by the different constructions from a pattern
+
+use std::collections::HashMap;
+use std::ops::Deref;
+use std::rc::Rc;
+
+use crate::derivative::nullable;
+use crate::dfa::{explore, Dfa};
+use crate::simplify::mkConcat;
+use crate::{extendedError, isExtended, parse, buildSimplified, Glushkov, Regex, Rule};
+use crate::Regex::{Empty, Eps, Letter, CharClass, Or, Concat, Star};
+
+// Nondeterministic automaton with transitions labelled by byte classes and ε-transitions,
+// state 0 being initial
+pub struct Nfa {
+    // What each state stands for in its construction, shown in DOT output
+    pub labels: Vec<String>,
+    pub finals: Vec<bool>,
+    pub edges: Vec<Vec<(Vec<bool>, usize)>>,
+    pub eps: Vec<Vec<usize>>
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Construction {
+    // Position automaton of augment and constructP/D/F
+    Glushkov,
+    // Partial derivative (equation) automaton
+    Antimirov,
+    // ε-NFA with one initial and one final state
+    Thompson,
+    // Ilie–Yu follow automaton, the Glushkov automaton with positions of equal follow sets merged
+    Follow
+}
+
+pub const CONSTRUCTIONS: [(&str, Construction); 4] = [
+    ("glushkov", Construction::Glushkov),
+    ("antimirov", Construction::Antimirov),
+    ("thompson", Construction::Thompson),
+    ("follow", Construction::Follow)
+];
+
+// Builds the automaton of a pattern with the chosen construction
+pub fn construct(regex_input: &str, construction: Construction) -> Result<Nfa, pest::error::Error<Rule>> {
+    let (x, all_state_labels) = parse(regex_input)?;
+    if isExtended(&x) {
+        return Err(extendedError(regex_input));
+    }
+    Ok(match construction {
+        Construction::Glushkov => buildSimplified(x, all_state_labels).to_nfa(),
+        Construction::Antimirov => antimirov(&x),
+        Construction::Thompson => thompson(&x),
+        Construction::Follow => buildSimplified(x, all_state_labels).to_follow_nfa()
+    })
+}
+
+impl Glushkov {
+    // Moves the label of every position onto the transitions entering it
+    pub fn to_nfa(&self) -> Nfa {
+        Nfa {
+            labels: (0..self.no_of_states).map(|x| format!("{}: {}", x, self.label(x))).collect(),
+            finals: (0..self.no_of_states).map(|x| self.is_final(x)).collect(),
+            edges: self.nfa.iter().map(|next| {
+                next.iter().map(|j| (self.state_letter[(j-1) as usize].clone(), *j as usize)).collect()
+            }).collect(),
+            eps: vec![Vec::new(); self.no_of_states as usize]
+        }
+    }
+
+    // Follow automaton as the quotient of the Glushkov automaton: states with the same follow
+    // set (the P set for state 0) and the same finality are merged, numbered in order of their
+    // first state so that state 0 stays initial
+    pub fn to_follow_nfa(&self) -> Nfa {
+        let mut ids: HashMap<(Vec<u8>, bool), usize> = HashMap::new();
+        let mut class: Vec<usize> = Vec::new();
+        let mut members: Vec<Vec<u8>> = Vec::new();
+        for i in 0..self.no_of_states {
+            let key = (self.nfa[i as usize].clone(), self.is_final(i));
+            let id = *ids.entry(key).or_insert(members.len());
+            if id == members.len() {
+                members.push(Vec::new());
+            }
+            members[id].push(i);
+            class.push(id);
+        }
+        let mut edges: Vec<Vec<(Vec<bool>, usize)>> = Vec::new();
+        for states in &members {
+            let mut out: Vec<(Vec<bool>, usize)> = Vec::new();
+            for j in &self.nfa[states[0] as usize] {
+                let c = &self.state_letter[(j-1) as usize];
+                let t = class[*j as usize];
+                match out.iter_mut().find(|(_, target)| *target == t) {
+                    Some((charvec, _)) => {
+                        for (x, y) in charvec.iter_mut().zip(c.iter()) {
+                            *x |= *y;
+                        }
+                    },
+                    None => out.push((c.clone(), t))
+                }
+            }
+            edges.push(out);
+        }
+        Nfa {
+            labels: members.iter().enumerate().map(|(i, states)| format!("{}: {:?}", i, states)).collect(),
+            finals: members.iter().map(|states| self.is_final(states[0])).collect(),
+            eps: vec![Vec::new(); members.len()],
+            edges
+        }
+    }
+}
+
+// Linear form of an expression: pairs of a class and a partial derivative, every string
+// starting with a byte of the class having its rest matched by the partial derivative
+fn linear_form(regexp: &Rc<Regex>) -> Vec<(Vec<bool>, Rc<Regex>)> {
+    match regexp.deref() {
+        Empty() | Eps() => Vec::new(),
+        Letter(a) => {
+            let mut charvec = vec![false; 256];
+            charvec[*a as usize] = true;
+            vec![(charvec, Rc::new(Eps()))]
+        },
+        CharClass(charvec) => vec![(charvec.clone(), Rc::new(Eps()))],
+        Or(r1, r2) => {
+            let mut res = linear_form(r1);
+            res.extend(linear_form(r2));
+            res
+        },
+        Concat(r1, r2) => {
+            let mut res: Vec<(Vec<bool>, Rc<Regex>)> = linear_form(r1).into_iter()
+                .map(|(c, t)| (c, mkConcat(&t, r2)))
+                .collect();
+            if nullable(r1) {
+                res.extend(linear_form(r2));
+            }
+            res
+        },
+        Star(r1) => linear_form(r1).into_iter().map(|(c, t)| (c, mkConcat(&t, regexp))).collect(),
+        _ => unreachable!()
+    }
+}
+
+// Antimirov automaton: the states are the expression and its partial derivatives, and the
+// classes leading from one state to the same partial derivative are merged into one transition
+pub fn antimirov(regexp: &Rc<Regex>) -> Nfa {
+    let mut ids: HashMap<Rc<Regex>, usize> = HashMap::new();
+    let mut terms: Vec<Rc<Regex>> = vec![regexp.clone()];
+    ids.insert(regexp.clone(), 0);
+    let mut edges: Vec<Vec<(Vec<bool>, usize)>> = Vec::new();
+    let mut i = 0;
+    while i < terms.len() {
+        let mut out: Vec<(Vec<bool>, usize)> = Vec::new();
+        for (c, t) in linear_form(&terms[i]) {
+            let id = match ids.get(&t) {
+                Some(id) => *id,
+                None => {
+                    ids.insert(t.clone(), terms.len());
+                    terms.push(t);
+                    terms.len() - 1
+                }
+            };
+            match out.iter_mut().find(|(_, target)| *target == id) {
+                Some((class, _)) => {
+                    for (x, y) in class.iter_mut().zip(c.iter()) {
+                        *x |= *y;
+                    }
+                },
+                None => out.push((c, id))
+            }
+        }
+        edges.push(out);
+        i += 1;
+    }
+    Nfa {
+        labels: terms.iter().enumerate().map(|(i, t)| format!("{}: {}", i, t)).collect(),
+        finals: terms.iter().map(nullable).collect(),
+        eps: vec![Vec::new(); terms.len()],
+        edges
+    }
+}
+
+impl Nfa {
+    fn add_state(&mut self) -> usize {
+        self.labels.push(self.finals.len().to_string());
+        self.finals.push(false);
+        self.edges.push(Vec::new());
+        self.eps.push(Vec::new());
+        self.finals.len() - 1
+    }
+}
+
+// Adds the Thompson fragment of an expression starting at the given state, returning its end;
+// a concatenation shares the end of its first operand with the start of the second
+fn thompson_into(regexp: &Rc<Regex>, start: usize, nfa: &mut Nfa) -> usize {
+    match regexp.deref() {
+        Empty() => nfa.add_state(),
+        Eps() => {
+            let end = nfa.add_state();
+            nfa.eps[start].push(end);
+            end
+        },
+        Letter(a) => {
+            let end = nfa.add_state();
+            let mut charvec = vec![false; 256];
+            charvec[*a as usize] = true;
+            nfa.edges[start].push((charvec, end));
+            end
+        },
+        CharClass(charvec) => {
+            let end = nfa.add_state();
+            nfa.edges[start].push((charvec.clone(), end));
+            end
+        },
+        Or(r1, r2) => {
+            let s1 = nfa.add_state();
+            let s2 = nfa.add_state();
+            nfa.eps[start].push(s1);
+            nfa.eps[start].push(s2);
+            let e1 = thompson_into(r1, s1, nfa);
+            let e2 = thompson_into(r2, s2, nfa);
+            let end = nfa.add_state();
+            nfa.eps[e1].push(end);
+            nfa.eps[e2].push(end);
+            end
+        },
+        Concat(r1, r2) => {
+            let mid = thompson_into(r1, start, nfa);
+            thompson_into(r2, mid, nfa)
+        },
+        Star(r1) => {
+            let s1 = nfa.add_state();
+            nfa.eps[start].push(s1);
+            let e1 = thompson_into(r1, s1, nfa);
+            let end = nfa.add_state();
+            nfa.eps[start].push(end);
+            nfa.eps[e1].push(s1);
+            nfa.eps[e1].push(end);
+            end
+        },
+        _ => unreachable!()
+    }
+}
+
+// Thompson ε-NFA, whose only final state is the end of the whole fragment
+pub fn thompson(regexp: &Rc<Regex>) -> Nfa {
+    let mut nfa = Nfa {
+        labels: Vec::new(),
+        finals: Vec::new(),
+        edges: Vec::new(),
+        eps: Vec::new()
+    };
+    let start = nfa.add_state();
+    let end = thompson_into(regexp, start, &mut nfa);
+    nfa.finals[end] = true;
+    nfa
+}
+
+impl Dfa {
+    // The DFA as an automaton of the common type, the bytes leading to the same state being
+    // merged into one transition and the transitions into dead states left out
+    pub fn to_nfa(&self) -> Nfa {
+        let n = self.no_of_states();
+        let mut edges: Vec<Vec<(Vec<bool>, usize)>> = Vec::new();
+        for state in 0..n {
+            let mut targets: Vec<usize> = self.trans[state].clone();
+            targets.sort();
+            targets.dedup();
+            edges.push(targets.into_iter()
+                .filter(|t| !self.is_dead(*t))
+                .map(|t| ((0..=255u8).map(|byte| self.next(state, byte) == t).collect(), t))
+                .collect());
+        }
+        Nfa {
+            labels: (0..n).map(|x| x.to_string()).collect(),
+            finals: self.finals.clone(),
+            eps: vec![Vec::new(); n],
+            edges
+        }
+    }
+}
+
+impl Nfa {
+    pub fn no_of_states(&self) -> usize {
+        self.finals.len()
+    }
+
+    pub fn no_of_transitions(&self) -> usize {
+        self.edges.iter().map(|x| x.len()).sum()
+    }
+
+    pub fn no_of_eps_transitions(&self) -> usize {
+        self.eps.iter().map(|x| x.len()).sum()
+    }
+
+    // Adds to a set of states every state reachable from them through ε-transitions, sorted
+    pub fn closure(&self, states: &mut Vec<usize>) {
+        let mut i = 0;
+        while i < states.len() {
+            for t in &self.eps[states[i]] {
+                if !states.contains(t) {
+                    states.push(*t);
+                }
+            }
+            i += 1;
+        }
+        states.sort();
+    }
+
+    // Subset construction, on ε-closed sets of states
+    pub fn determinise(&self) -> Dfa {
+        let mut start: Vec<usize> = vec![0];
+        self.closure(&mut start);
+        explore(start, self.byte_classes(), |states, byte| {
+            let mut next: Vec<usize> = Vec::new();
+            for s in states {
+                for (class, t) in &self.edges[*s] {
+                    if class[byte as usize] && !next.contains(t) {
+                        next.push(*t);
+                    }
+                }
+            }
+            self.closure(&mut next);
+            next
+        }, |states| states.iter().any(|s| self.finals[*s]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compile;
+    use crate::tests::all_strings;
+
+    const PATTERNS: [&str; 10] = [
+        "a", "(a|b)*abb", "a?b?c?", "(ab|ba)*c", "[ab]{2,3}", "(a*b*)*c", "a(b|c)*|ca", "(()|a)b*", "((a|b)c)+", "a*a*",
+    ];
+
+    // Runs an automaton of every construction on the same strings as the Glushkov automaton
+    fn assert_agrees(construction: Construction) {
+        let strings = all_strings(b"abc", 6);
+        for pattern in PATTERNS {
+            let g = compile(pattern).unwrap();
+            let d = construct(pattern, construction).unwrap().determinise();
+            for s in &strings {
+                assert_eq!(d.accepts(s), g.accepts(s), "{:?} {} on {:?}", construction, pattern, s);
+            }
+        }
+    }
+
+    #[test]
+    fn antimirov_agrees_with_glushkov() {
+        assert_agrees(Construction::Antimirov);
+    }
+
+    // The partial derivative automaton is a quotient of the position automaton
+    #[test]
+    fn antimirov_is_no_larger() {
+        for pattern in PATTERNS {
+            let g = construct(pattern, Construction::Glushkov).unwrap();
+            let a = construct(pattern, Construction::Antimirov).unwrap();
+            assert!(a.no_of_states() <= g.no_of_states(), "{}", pattern);
+            assert_eq!(a.no_of_eps_transitions(), 0, "{}", pattern);
+        }
+        assert_eq!(construct("(a|b)*abb", Construction::Antimirov).unwrap().no_of_states(), 4);
+    }
+
+    #[test]
+    fn thompson_and_follow_agree_with_glushkov() {
+        assert_agrees(Construction::Thompson);
+        assert_agrees(Construction::Follow);
+    }
+
+    #[test]
+    fn sizes() {
+        for pattern in PATTERNS {
+            let g = construct(pattern, Construction::Glushkov).unwrap();
+            let f = construct(pattern, Construction::Follow).unwrap();
+            assert!(f.no_of_states() <= g.no_of_states(), "{}", pattern);
+        }
+        // Two states for each letter and two for the alternation, whose initial state has ε-transitions to both letters
+        let t = construct("a|b", Construction::Thompson).unwrap();
+        assert_eq!(t.no_of_states(), 6);
+        assert_eq!(t.finals.iter().filter(|x| **x).count(), 1);
+        let mut states = vec![0];
+        t.closure(&mut states);
+        states.sort();
+        assert_eq!(states.len(), 3);
+    }
+
+    // All the engines on the same inputs: every construction, the derivative DFA, the DFA of
+    // the Glushkov automaton and the automaton back from a DFA
+    #[test]
+    fn every_engine_agrees() {
+        let strings = all_strings(b"abc", 5);
+        for pattern in PATTERNS {
+            let g = compile(pattern).unwrap();
+            let (x, _) = parse(pattern).unwrap();
+            let mut dfas: Vec<Dfa> = CONSTRUCTIONS.iter().map(|(_, c)| construct(pattern, *c).unwrap().determinise()).collect();
+            dfas.push(crate::derivative::toDfa(&x));
+            dfas.push(crate::dfa::fromRegex(&x));
+            dfas.push(crate::dfa::fromRegex(&x).to_nfa().determinise());
+            for s in &strings {
+                for d in &dfas {
+                    assert_eq!(d.accepts(s), g.accepts(s), "{} on {:?}", pattern, s);
+                }
+            }
+        }
+    }
+}
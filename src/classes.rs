@@ -0,0 +1,166 @@
+// Byte equivalence classes: the coarsest partition of the bytes that no letter or class of a
+// pattern tells apart, so that DFAs need one transition per class instead of one per byte
+
+use std::collections::HashMap;
+use std::ops::Deref;
+use std::rc::Rc;
+
+use crate::automaton::Nfa;
+use crate::{Glushkov, Regex};
+use crate::Regex::{Letter, CharClass, Or, Concat, Star, And, Not};
+
+// Splits every class of the partition by whether its bytes satisfy the predicate, numbering
+// the classes in the order of their smallest byte
+fn split(classes: &[u8], predicate: &[bool]) -> Vec<u8> {
+    let mut ids: HashMap<(u8, bool), u8> = HashMap::new();
+    let mut res: Vec<u8> = Vec::with_capacity(256);
+    for byte in 0..256 {
+        let len = ids.len() as u8;
+        res.push(*ids.entry((classes[byte], predicate[byte])).or_insert(len));
+    }
+    res
+}
+
+// Coarsest partition of the bytes, as the class of every byte, that keeps each predicate whole
+pub fn partition<'a, I: IntoIterator<Item = &'a Vec<bool>>>(predicates: I) -> Vec<u8> {
+    predicates.into_iter().fold(vec![0; 256], |classes, p| split(&classes, p))
+}
+
+// Common refinement of two partitions, for DFAs built from two others
+pub fn refine(c1: &[u8], c2: &[u8]) -> Vec<u8> {
+    let mut ids: HashMap<(u8, u8), u8> = HashMap::new();
+    let mut res: Vec<u8> = Vec::with_capacity(256);
+    for byte in 0..256 {
+        let len = ids.len() as u8;
+        res.push(*ids.entry((c1[byte], c2[byte])).or_insert(len));
+    }
+    res
+}
+
+// Smallest byte of every class
+pub fn representatives(classes: &[u8]) -> Vec<u8> {
+    let mut res: Vec<u8> = Vec::new();
+    for (byte, c) in classes.iter().enumerate() {
+        if *c as usize == res.len() {
+            res.push(byte as u8);
+        }
+    }
+    res
+}
+
+// Bytes of every class
+pub fn members(classes: &[u8]) -> Vec<Vec<u8>> {
+    let mut res: Vec<Vec<u8>> = vec![Vec::new(); representatives(classes).len()];
+    for (byte, c) in classes.iter().enumerate() {
+        res[*c as usize].push(byte as u8);
+    }
+    res
+}
+
+fn predicates(regexp: &Rc<Regex>, res: &mut Vec<Vec<bool>>) {
+    match regexp.deref() {
+        Letter(a) => {
+            let mut charvec = vec![false; 256];
+            charvec[*a as usize] = true;
+            res.push(charvec);
+        },
+        CharClass(charvec) => res.push(charvec.clone()),
+        Or(r1, r2) | Concat(r1, r2) | And(r1, r2) => {
+            predicates(r1, res);
+            predicates(r2, res);
+        },
+        Star(r1) | Not(r1) => predicates(r1, res),
+        _ => {}
+    }
+}
+
+// Byte classes of an AST, which its derivatives do not tell apart either
+pub fn byte_classes(regexp: &Rc<Regex>) -> Vec<u8> {
+    let mut res: Vec<Vec<bool>> = Vec::new();
+    predicates(regexp, &mut res);
+    partition(&res)
+}
+
+impl Glushkov {
+    // Byte classes of the labels of the positions
+    pub fn byte_classes(&self) -> Vec<u8> {
+        partition(&self.state_letter)
+    }
+}
+
+impl Nfa {
+    // Byte classes of the labels of the transitions
+    pub fn byte_classes(&self) -> Vec<u8> {
+        partition(self.edges.iter().flatten().map(|(class, _)| class))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::derivative::derive;
+    use crate::{compile, parse};
+
+    fn class_of(bytes: &[u8]) -> Vec<bool> {
+        (0..=255u8).map(|x| bytes.contains(&x)).collect()
+    }
+
+    // Two bytes share a class exactly when no predicate tells them apart, and the classes are
+    // numbered in the order of their smallest byte
+    #[test]
+    fn coarsest_partition() {
+        let predicates = vec![class_of(b"abc"), class_of(b"bcd"), class_of(b"x")];
+        let classes = partition(&predicates);
+        for x in 0..256 {
+            for y in 0..256 {
+                let same = predicates.iter().all(|p| p[x] == p[y]);
+                assert_eq!(classes[x] == classes[y], same, "{} {}", x, y);
+            }
+        }
+        let reps = representatives(&classes);
+        assert_eq!(reps, vec![0, b'a', b'b', b'd', b'x']);
+        for (c, bytes) in members(&classes).iter().enumerate() {
+            assert_eq!(bytes[0], reps[c]);
+            assert!(bytes.iter().all(|x| classes[*x as usize] as usize == c));
+        }
+        assert_eq!(members(&classes).iter().map(|m| m.len()).sum::<usize>(), 256);
+    }
+
+    #[test]
+    fn refinement() {
+        let c1 = partition(&vec![class_of(b"ab")]);
+        let c2 = partition(&vec![class_of(b"bc")]);
+        let classes = refine(&c1, &c2);
+        for x in 0..256 {
+            for y in 0..256 {
+                assert_eq!(classes[x] == classes[y], c1[x] == c1[y] && c2[x] == c2[y], "{} {}", x, y);
+            }
+        }
+        assert_eq!(representatives(&classes), vec![0, b'a', b'b', b'c']);
+    }
+
+    // Bytes of one class lead every state of the automaton to the same states
+    #[test]
+    fn bytes_of_a_class_behave_the_same() {
+        for pattern in ["a[b-d]*x", "[^a]b|[a-c]", "(.a|[0-9]+)&~(1.*)", "\\x00[\\x80-\\xff]"] {
+            let (x, _) = parse(pattern).unwrap();
+            let classes = byte_classes(&x);
+            let reps = representatives(&classes);
+            for byte in 0..=255u8 {
+                let rep = reps[classes[byte as usize] as usize];
+                assert_eq!(derive(&x, byte), derive(&x, rep), "{} {}", pattern, byte);
+            }
+        }
+        for pattern in ["a[b-d]*x", "[^a]b|[a-c]", "\\x00[\\x80-\\xff]", "(ab|[a-z])*[0-9]"] {
+            let g = compile(pattern).unwrap();
+            let classes = g.byte_classes();
+            let reps = representatives(&classes);
+            for state in 0..g.no_of_states {
+                for byte in 0..=255u8 {
+                    let rep = reps[classes[byte as usize] as usize];
+                    assert_eq!(g.step(&[state], byte), g.step(&[state], rep), "{} {} {}", pattern, state, byte);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,86 @@
+// Counting the strings matched by a pattern, per length and in total for finite languages
+
+use crate::dfa::Dfa;
+
+impl Dfa {
+    // Number of accepted strings of length n, saturating at u128::MAX
+    pub fn count(&self, n: usize) -> u128 {
+        self.path_counts(n)[n][0]
+    }
+
+    // The language is infinite exactly when a cycle goes through live states, all states being reachable
+    pub fn is_finite(&self) -> bool {
+        let live = self.live_states();
+        // 0 : not visited, 1 : on the current path, 2 : done
+        let mut colour: Vec<u8> = vec![0; self.no_of_states()];
+        for root in 0..self.no_of_states() {
+            if !live[root] || colour[root] != 0 {
+                continue;
+            }
+            let mut stack: Vec<(usize, usize)> = vec![(root, 0)];
+            colour[root] = 1;
+            while let Some((s, class)) = stack.pop() {
+                if class == self.no_of_classes() {
+                    colour[s] = 2;
+                    continue;
+                }
+                stack.push((s, class + 1));
+                let t = self.trans[s][class];
+                if !live[t] {
+                    continue;
+                }
+                if colour[t] == 1 {
+                    return false;
+                }
+                if colour[t] == 0 {
+                    colour[t] = 1;
+                    stack.push((t, 0));
+                }
+            }
+        }
+        true
+    }
+
+    // Total number of accepted strings, None for infinite languages; no string of a finite
+    // language is longer than the number of states
+    pub fn cardinality(&self) -> Option<u128> {
+        if !self.is_finite() {
+            return None;
+        }
+        let n = self.no_of_states();
+        let counts = self.path_counts(n);
+        Some(counts.iter().fold(0u128, |total, row| total.saturating_add(row[0])))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dfa;
+    use crate::tests::all_strings;
+
+    #[test]
+    fn counts_against_brute_force() {
+        let strings = all_strings(b"abc", 6);
+        for pattern in ["(a|b)*abb", "a*b*", "(ab|ba|c)*", "a{2,4}|[bc]+", "(a|b)*&~(.*aa.*)"] {
+            let d = dfa::compile(pattern).unwrap();
+            for n in 0..=6 {
+                let expected = strings.iter().filter(|s| s.len() == n && d.accepts(s)).count() as u128;
+                assert_eq!(d.count(n), expected, "{} at {}", pattern, n);
+            }
+        }
+        assert_eq!(dfa::compile(".").unwrap().count(1), 256);
+        assert_eq!(dfa::compile("..").unwrap().count(2), 65536);
+        assert_eq!(dfa::compile(".*").unwrap().count(100), u128::MAX);
+    }
+
+    #[test]
+    fn cardinality() {
+        assert_eq!(dfa::compile("a|bc|()").unwrap().cardinality(), Some(3));
+        assert_eq!(dfa::compile("[a-c]{2}").unwrap().cardinality(), Some(9));
+        assert_eq!(dfa::compile("(a|b)*&.{,2}").unwrap().cardinality(), Some(7));
+        assert_eq!(dfa::compile("a&b").unwrap().cardinality(), Some(0));
+        assert_eq!(dfa::compile("a*").unwrap().cardinality(), None);
+        assert!(dfa::compile("ab*&a").unwrap().is_finite());
+        assert!(!dfa::compile("(ab)*c").unwrap().is_finite());
+    }
+}
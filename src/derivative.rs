@@ -0,0 +1,198 @@
+// Matching by Brzozowski derivatives, the derivative of an expression by a byte matching
+// exactly the rest of the strings that start with that byte
+
+use std::collections::HashMap;
+use std::ops::Deref;
+use std::rc::Rc;
+
+use crate::classes::byte_classes;
+use crate::dfa::{explore, Dfa};
+use crate::simplify::{mkConcat, mkNot, mkStar};
+use crate::{findLambda, Regex};
+use crate::Regex::{Empty, Eps, Letter, CharClass, Or, Concat, Star, And, Not};
+
+pub fn nullable(regexp: &Rc<Regex>) -> bool {
+    matches!(findLambda(regexp).deref(), Eps())
+}
+
+fn operands(regexp: &Rc<Regex>, and: bool, items: &mut Vec<Rc<Regex>>) {
+    match regexp.deref() {
+        Or(r1, r2) if !and => {
+            operands(r1, and, items);
+            operands(r2, and, items);
+        },
+        And(r1, r2) if and => {
+            operands(r1, and, items);
+            operands(r2, and, items);
+        },
+        _ => items.push(regexp.clone())
+    }
+}
+
+// Rebuilds nested Or or And nodes from their operands sorted and without duplicates, so that
+// derivatives equal up to associativity, commutativity and idempotence are the same expression;
+// this keeps the number of distinct derivatives finite
+fn normalise(r1: &Rc<Regex>, r2: &Rc<Regex>, and: bool) -> Rc<Regex> {
+    let mut items: Vec<Rc<Regex>> = Vec::new();
+    operands(r1, and, &mut items);
+    operands(r2, and, &mut items);
+    if and {
+        if items.iter().any(|r| matches!(r.deref(), Empty())) {
+            return Rc::new(Empty());
+        }
+    } else {
+        items.retain(|r| !matches!(r.deref(), Empty()));
+    }
+    items.sort();
+    items.dedup();
+    let mut res = match items.pop() {
+        Some(r) => r,
+        None => return Rc::new(Empty())
+    };
+    while let Some(r) = items.pop() {
+        res = if and { Rc::new(And(r, res)) } else { Rc::new(Or(r, res)) };
+    }
+    res
+}
+
+pub fn dOr(r1: &Rc<Regex>, r2: &Rc<Regex>) -> Rc<Regex> {
+    normalise(r1, r2, false)
+}
+
+pub fn dAnd(r1: &Rc<Regex>, r2: &Rc<Regex>) -> Rc<Regex> {
+    normalise(r1, r2, true)
+}
+
+// Derivative of an expression by a byte
+pub fn derive(regexp: &Rc<Regex>, byte: u8) -> Rc<Regex> {
+    match regexp.deref() {
+        Empty() | Eps() => Rc::new(Empty()),
+        Letter(a) => if *a == byte { Rc::new(Eps()) } else { Rc::new(Empty()) },
+        CharClass(charvec) => if charvec[byte as usize] { Rc::new(Eps()) } else { Rc::new(Empty()) },
+        Or(r1, r2) => dOr(&derive(r1, byte), &derive(r2, byte)),
+        Concat(r1, r2) => {
+            let first = mkConcat(&derive(r1, byte), r2);
+            if nullable(r1) {
+                dOr(&first, &derive(r2, byte))
+            } else {
+                first
+            }
+        },
+        Star(r1) => mkConcat(&derive(r1, byte), &mkStar(r1)),
+        And(r1, r2) => dAnd(&derive(r1, byte), &derive(r2, byte)),
+        Not(r1) => mkNot(&derive(r1, byte))
+    }
+}
+
+// Normalises an expression bottom up with the same smart constructors, so that it is the
+// representative of the first derivative state
+fn normal_form(regexp: &Rc<Regex>) -> Rc<Regex> {
+    match regexp.deref() {
+        Or(r1, r2) => dOr(&normal_form(r1), &normal_form(r2)),
+        And(r1, r2) => dAnd(&normal_form(r1), &normal_form(r2)),
+        Concat(r1, r2) => mkConcat(&normal_form(r1), &normal_form(r2)),
+        Star(r1) => mkStar(&normal_form(r1)),
+        Not(r1) => mkNot(&normal_form(r1)),
+        _ => regexp.clone()
+    }
+}
+
+// Derivative automaton built lazily while matching: each distinct derivative met is a state,
+// and every transition computed is remembered
+pub struct Derivatives {
+    ids: HashMap<Rc<Regex>, usize>,
+    exprs: Vec<Rc<Regex>>,
+    finals: Vec<bool>,
+    trans: Vec<Vec<Option<usize>>>
+}
+
+impl Derivatives {
+    pub fn new(regexp: &Rc<Regex>) -> Derivatives {
+        let mut d = Derivatives {
+            ids: HashMap::new(),
+            exprs: Vec::new(),
+            finals: Vec::new(),
+            trans: Vec::new()
+        };
+        d.state(normal_form(regexp));
+        d
+    }
+
+    fn state(&mut self, regexp: Rc<Regex>) -> usize {
+        if let Some(id) = self.ids.get(&regexp) {
+            return *id;
+        }
+        let id = self.exprs.len();
+        self.finals.push(nullable(&regexp));
+        self.trans.push(vec![None; 256]);
+        self.ids.insert(regexp.clone(), id);
+        self.exprs.push(regexp);
+        id
+    }
+
+    fn next(&mut self, state: usize, byte: u8) -> usize {
+        if let Some(next) = self.trans[state][byte as usize] {
+            return next;
+        }
+        let r = derive(&self.exprs[state], byte);
+        let next = self.state(r);
+        self.trans[state][byte as usize] = Some(next);
+        next
+    }
+
+    pub fn is_match(&mut self, s: &[u8]) -> bool {
+        let mut curr = 0;
+        for byte in s {
+            curr = self.next(curr, *byte);
+        }
+        self.finals[curr]
+    }
+}
+
+// Builds the whole derivative DFA, used as a reference to check the other constructions against
+pub fn toDfa(regexp: &Rc<Regex>) -> Dfa {
+    explore(normal_form(regexp), byte_classes(regexp), derive, nullable)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::equiv::{equivalentDfa, Comparison};
+    use crate::tests::all_strings;
+    use crate::{compile, dfa, parse};
+
+    fn ast(pattern: &str) -> Rc<Regex> {
+        parse(pattern).unwrap().0
+    }
+
+    #[test]
+    fn derivatives() {
+        assert_eq!(derive(&ast("ab"), b'a'), Rc::new(Letter(b'b')));
+        assert_eq!(derive(&ast("ab"), b'b'), Rc::new(Empty()));
+        assert_eq!(derive(&ast("a*"), b'a'), ast("a*"));
+        assert_eq!(derive(&ast("a|ab"), b'a'), dOr(&Rc::new(Eps()), &Rc::new(Letter(b'b'))));
+        assert!(nullable(&derive(&ast("~a"), b'b')));
+    }
+
+    // Lazy derivative matching against the Glushkov automaton
+    #[test]
+    fn agrees_with_glushkov() {
+        let strings = all_strings(b"abc", 6);
+        for pattern in ["(a|b)*abb", "a?b?c?", "(ab|ba)*c", "[ab]{2,3}", "(a*b*)*c", "a(b|c)*|ca"] {
+            let g = compile(pattern).unwrap();
+            let mut d = Derivatives::new(&ast(pattern));
+            for s in &strings {
+                assert_eq!(d.is_match(s), g.accepts(s), "{} on {:?}", pattern, s);
+            }
+        }
+    }
+
+    // The whole derivative DFA against the DFA built through Glushkov and the & and ~ constructions
+    #[test]
+    fn agrees_with_dfa() {
+        for pattern in ["(a|b)*abb", "~(a*)b", "(a|b)*&~(.*aa.*)", "((ab)*&(a|b)*a)*", "~~(a|())", ".*a.{3}"] {
+            let x = ast(pattern);
+            assert!(matches!(equivalentDfa(&toDfa(&x), &dfa::fromRegex(&x)), Comparison::Holds), "{}", pattern);
+        }
+    }
+}
@@ -0,0 +1,82 @@
+// Deterministic (one-unambiguous) expressions, as required of XML Schema and DTD content
+// models: no two positions with a common byte may follow the same state
+
+use crate::explain::show_byte;
+use crate::Glushkov;
+
+// Two positions that can both be entered from the same state on the same byte
+pub struct Conflict {
+    pub from: u8,
+    pub first: u8,
+    pub second: u8,
+    pub byte: u8
+}
+
+impl Glushkov {
+    // First conflict found among the P set (followers of state 0) and the F sets of the positions
+    pub fn conflict(&self) -> Option<Conflict> {
+        for (from, next) in self.nfa.iter().enumerate() {
+            for (a, first) in next.iter().enumerate() {
+                for second in &next[a+1..] {
+                    let c1 = &self.state_letter[(first-1) as usize];
+                    let c2 = &self.state_letter[(second-1) as usize];
+                    if let Some(byte) = (0..256).find(|x| c1[*x] && c2[*x]) {
+                        return Some(Conflict {
+                            from: from as u8,
+                            first: *first,
+                            second: *second,
+                            byte: byte as u8
+                        });
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    pub fn is_deterministic(&self) -> bool {
+        self.conflict().is_none()
+    }
+
+    // Describes a conflict with the labels of the positions involved
+    pub fn describe_conflict(&self, c: &Conflict) -> String {
+        format!("after {} ({}), positions {} ({}) and {} ({}) both match '{}'",
+            c.from, self.label(c.from), c.first, self.label(c.first), c.second, self.label(c.second), show_byte(c.byte))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::compile_unsimplified;
+    use crate::tests::all_strings;
+
+    #[test]
+    fn deterministic_patterns() {
+        let strings = all_strings(b"abc", 5);
+        for pattern in ["a(b|c)*", "(ab)*", "a?b", "b*a(b*a)*", "(a|b)c|c", "[ab]*c"] {
+            let g = compile_unsimplified(pattern).unwrap();
+            assert!(g.is_deterministic(), "{}", pattern);
+            // At most one position is ever active
+            for s in &strings {
+                let mut curr: Vec<u8> = vec![0];
+                for byte in s {
+                    curr = g.step(&curr, *byte);
+                    assert!(curr.len() <= 1, "{} on {:?}", pattern, s);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn conflicts() {
+        for pattern in ["a?a", "(a|b)*a", "ab|ac", "[ab]|b", "(a|())a", "a*[a-c]"] {
+            let g = compile_unsimplified(pattern).unwrap();
+            let c = g.conflict().unwrap_or_else(|| panic!("{}", pattern));
+            assert_ne!(c.first, c.second);
+            let next = g.step(&[c.from], c.byte);
+            assert!(next.contains(&c.first) && next.contains(&c.second), "{}", pattern);
+        }
+        let g = compile_unsimplified("ab|ac").unwrap();
+        assert_eq!(g.describe_conflict(&g.conflict().unwrap()), "after 0 (start), positions 1 (a) and 3 (a) both match 'a'");
+    }
+}
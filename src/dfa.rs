@@ -0,0 +1,285 @@
+// Determinisation and minimisation of the Glushkov automaton, and the product, complement,
+// concatenation and star constructions on DFAs used for patterns with & and ~
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::ops::Deref;
+use std::rc::Rc;
+
+use crate::classes::{refine, representatives};
+use crate::simplify::simplify;
+use crate::{fromAST, isExtended, parse, Glushkov, Regex, Rule};
+use crate::Regex::{Or, Concat, Star, And, Not};
+
+// Complete deterministic automaton over bytes, state 0 being the initial state; the bytes are
+// grouped into classes that behave the same, with one transition per class
+#[derive(Debug, Clone)]
+pub struct Dfa {
+    // Class of every byte
+    pub classes: Vec<u8>,
+    // trans[state][class]
+    pub trans: Vec<Vec<usize>>,
+    pub finals: Vec<bool>
+}
+
+// Builds the DFA of all states reachable from start, numbering them in breadth-first order;
+// step is only called on the smallest byte of every class
+pub fn explore<K, S, A>(start: K, classes: Vec<u8>, step: S, accept: A) -> Dfa
+where
+    K: Clone + Eq + Hash,
+    S: Fn(&K, u8) -> K,
+    A: Fn(&K) -> bool
+{
+    let mut ids: HashMap<K, usize> = HashMap::new();
+    let mut queue: Vec<K> = vec![start.clone()];
+    ids.insert(start, 0);
+    let mut trans: Vec<Vec<usize>> = Vec::new();
+    let mut finals: Vec<bool> = Vec::new();
+    let reps = representatives(&classes);
+    let mut i = 0;
+    while i < queue.len() {
+        let curr = queue[i].clone();
+        finals.push(accept(&curr));
+        let mut row: Vec<usize> = Vec::with_capacity(reps.len());
+        for byte in &reps {
+            let next = step(&curr, *byte);
+            let id = match ids.get(&next) {
+                Some(id) => *id,
+                None => {
+                    let id = queue.len();
+                    ids.insert(next.clone(), id);
+                    queue.push(next);
+                    id
+                }
+            };
+            row.push(id);
+        }
+        trans.push(row);
+        i += 1;
+    }
+    Dfa { classes, trans, finals }
+}
+
+// Subset construction on the Glushkov automaton, each DFA state being a sorted set of positions
+pub fn determinise(g: &Glushkov) -> Dfa {
+    explore(vec![0u8], g.byte_classes(), |states, byte| g.step(states, byte), |states| states.iter().any(|x| g.is_final(*x)))
+}
+
+// Compiles a regular expression, possibly using & and ~, into a DFA
+pub fn compile(regex_input: &str) -> Result<Dfa, pest::error::Error<Rule>> {
+    let (x, _) = parse(regex_input)?;
+    Ok(fromRegex(&simplify(&x)))
+}
+
+// Builds the DFA of an AST: subexpressions without & and ~ go through the Glushkov automaton,
+// the operators above them through constructions on the DFAs of their operands
+pub fn fromRegex(regexp: &Rc<Regex>) -> Dfa {
+    if !isExtended(regexp) {
+        return determinise(&fromAST(regexp.clone()));
+    }
+    match regexp.deref() {
+        And(r1, r2) => product(&fromRegex(r1), &fromRegex(r2), |a, b| a && b),
+        Or(r1, r2) => product(&fromRegex(r1), &fromRegex(r2), |a, b| a || b),
+        Not(r1) => complement(&fromRegex(r1)),
+        Concat(r1, r2) => concat(&fromRegex(r1), &fromRegex(r2)),
+        Star(r1) => star(&fromRegex(r1)),
+        _ => unreachable!()
+    }
+}
+
+// Runs both DFAs side by side, a pair of states accepting when op says so
+pub fn product(d1: &Dfa, d2: &Dfa, op: fn(bool, bool) -> bool) -> Dfa {
+    explore((0usize, 0usize), refine(&d1.classes, &d2.classes), |(p, q), byte| {
+        (d1.next(*p, byte), d2.next(*q, byte))
+    }, |(p, q)| op(d1.finals[*p], d2.finals[*q])).minimise()
+}
+
+// Swaps accepting and non-accepting states, the DFA being complete
+pub fn complement(d: &Dfa) -> Dfa {
+    Dfa {
+        classes: d.classes.clone(),
+        trans: d.trans.clone(),
+        finals: d.finals.iter().map(|x| !x).collect()
+    }
+}
+
+// Runs d1 while keeping the set of states of every run of d2 started where d1 accepted
+pub fn concat(d1: &Dfa, d2: &Dfa) -> Dfa {
+    let start: Vec<usize> = if d1.finals[0] { vec![0] } else { Vec::new() };
+    explore((0usize, start), refine(&d1.classes, &d2.classes), |(p, set), byte| {
+        let p1 = d1.next(*p, byte);
+        let mut set1: Vec<usize> = set.iter().map(|q| d2.next(*q, byte)).collect();
+        if d1.finals[p1] {
+            set1.push(0);
+        }
+        set1.sort();
+        set1.dedup();
+        (p1, set1)
+    }, |(_, set)| set.iter().any(|q| d2.finals[*q])).minimise()
+}
+
+// Keeps the set of states of all runs of d, starting a new run whenever one of them accepts;
+// the flag marks the initial state, which accepts the empty string
+pub fn star(d: &Dfa) -> Dfa {
+    explore((true, vec![0usize]), d.classes.clone(), |(_, set), byte| {
+        let mut set1: Vec<usize> = set.iter().map(|q| d.next(*q, byte)).collect();
+        if set1.iter().any(|q| d.finals[*q]) {
+            set1.push(0);
+        }
+        set1.sort();
+        set1.dedup();
+        (false, set1)
+    }, |(initial, set)| *initial || set.iter().any(|q| d.finals[*q])).minimise()
+}
+
+impl Dfa {
+    pub fn no_of_states(&self) -> usize {
+        self.finals.len()
+    }
+
+    pub fn no_of_classes(&self) -> usize {
+        self.trans.first().map_or(0, |row| row.len())
+    }
+
+    // Transition on a byte, through its class
+    pub fn next(&self, state: usize, byte: u8) -> usize {
+        self.trans[state][self.classes[byte as usize] as usize]
+    }
+
+    // Runs the DFA on a string
+    pub fn accepts(&self, s: &[u8]) -> bool {
+        let mut curr = 0;
+        for byte in s {
+            curr = self.next(curr, *byte);
+        }
+        self.finals[curr]
+    }
+
+    // Checks whether a state is a non-accepting state that loops on every byte
+    pub fn is_dead(&self, state: usize) -> bool {
+        !self.finals[state] && self.trans[state].iter().all(|x| *x == state)
+    }
+
+    // Minimises the DFA by Moore's partition refinement, starting from the accepting/non-accepting split
+    pub fn minimise(&self) -> Dfa {
+        let n = self.no_of_states();
+        let mut block: Vec<usize> = self.finals.iter().map(|x| if *x { 1 } else { 0 }).collect();
+        let mut no_of_blocks = 0;
+        loop {
+            // Two states stay together only if their blocks and the blocks of all their successors agree
+            let mut ids: HashMap<Vec<usize>, usize> = HashMap::new();
+            let mut next_block: Vec<usize> = vec![0; n];
+            for s in 0..n {
+                let mut signature: Vec<usize> = Vec::with_capacity(self.no_of_classes() + 1);
+                signature.push(block[s]);
+                for t in &self.trans[s] {
+                    signature.push(block[*t]);
+                }
+                let len = ids.len();
+                next_block[s] = *ids.entry(signature).or_insert(len);
+            }
+            block = next_block;
+            if ids.len() == no_of_blocks {
+                break;
+            }
+            no_of_blocks = ids.len();
+        }
+        // Renumber the blocks from the block of the initial state, through one representative state each
+        let mut rep: Vec<usize> = vec![0; no_of_blocks];
+        for s in (0..n).rev() {
+            rep[block[s]] = s;
+        }
+        explore(block[0], self.classes.clone(), |b, byte| block[self.next(rep[*b], byte)], |b| self.finals[rep[*b]])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::all_strings;
+
+    const PATTERNS: [&str; 10] = [
+        "a", "ab*", "(a|b)*abb", "a?b?c?", "(ab|ba)*", "[ab]c|a+", "(a|b){2,3}", "c*(a|())b", "a{,2}c", "(a*b*)*",
+    ];
+
+    fn compile_dfa(pattern: &str) -> Dfa {
+        crate::compile(pattern).map(|g| determinise(&g)).unwrap()
+    }
+
+    #[test]
+    fn determinise_agrees_with_glushkov() {
+        let strings = all_strings(b"abc", 6);
+        for pattern in PATTERNS {
+            let g = crate::compile(pattern).unwrap();
+            let d = determinise(&g);
+            for s in &strings {
+                assert_eq!(d.accepts(s), g.accepts(s), "{} on {:?}", pattern, s);
+            }
+        }
+    }
+
+    #[test]
+    fn minimise_keeps_the_language() {
+        let strings = all_strings(b"abc", 6);
+        for pattern in PATTERNS {
+            let d = compile_dfa(pattern);
+            let m = d.minimise();
+            assert!(m.no_of_states() <= d.no_of_states(), "{}", pattern);
+            assert_eq!(m.minimise().no_of_states(), m.no_of_states(), "{}", pattern);
+            for s in &strings {
+                assert_eq!(m.accepts(s), d.accepts(s), "{} on {:?}", pattern, s);
+            }
+        }
+    }
+
+    // Sizes of the minimal complete DFAs, dead state included
+    #[test]
+    fn minimal_sizes() {
+        assert_eq!(compile_dfa("a*").minimise().no_of_states(), 2);
+        assert_eq!(compile_dfa("a|b").minimise().no_of_states(), 3);
+        assert_eq!(compile_dfa("(a|b)*abb").minimise().no_of_states(), 5);
+        assert_eq!(compile_dfa("(a|b)*a(a|b)").minimise().no_of_states(), 5);
+        assert_eq!(compile_dfa("aa*|a+|a(a)*").minimise().no_of_states(), 3);
+    }
+
+    // Matches a whole string against an AST straight from the definitions of the operators
+    fn brute(regexp: &Rc<Regex>, s: &[u8]) -> bool {
+        match regexp.deref() {
+            Regex::Empty() => false,
+            Regex::Eps() => s.is_empty(),
+            Regex::Letter(a) => s == [*a],
+            Regex::CharClass(charvec) => s.len() == 1 && charvec[s[0] as usize],
+            Or(r1, r2) => brute(r1, s) || brute(r2, s),
+            And(r1, r2) => brute(r1, s) && brute(r2, s),
+            Not(r1) => !brute(r1, s),
+            Concat(r1, r2) => (0..=s.len()).any(|k| brute(r1, &s[..k]) && brute(r2, &s[k..])),
+            Star(_) => s.is_empty() || (1..=s.len()).any(|k| brute(&star_operand(regexp), &s[..k]) && brute(regexp, &s[k..]))
+        }
+    }
+
+    fn star_operand(regexp: &Rc<Regex>) -> Rc<Regex> {
+        match regexp.deref() {
+            Star(r1) => r1.clone(),
+            _ => unreachable!()
+        }
+    }
+
+    // Product, complement, concatenation and star of DFAs against the definitions
+    #[test]
+    fn extended_operators() {
+        let patterns = [
+            "~a", "~(ab)*", "a*&(aa)*", "(a|b)*&~(.*bb.*)", "(~a)b", "(a&a*)*", "~()", "(.*a.*)&(.*b.*)&~(.*c.*)",
+            "(~(a*))*", "a(b&~c)*", "~(a|b)c", "(ab|a)&~(ab)",
+        ];
+        let strings = all_strings(b"abc", 5);
+        for pattern in patterns {
+            let (x, _) = parse(pattern).unwrap();
+            let d = compile(pattern).unwrap();
+            let m = d.minimise();
+            for s in &strings {
+                assert_eq!(d.accepts(s), brute(&x, s), "{} on {:?}", pattern, s);
+                assert_eq!(m.accepts(s), d.accepts(s), "{} on {:?}", pattern, s);
+            }
+        }
+    }
+}
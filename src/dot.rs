@@ -0,0 +1,139 @@
+// Graphviz DOT export of the Glushkov automaton, of the other NFA constructions, of DFAs and of
+// symbolic automata
+
+use crate::automaton::Nfa;
+use crate::dfa::Dfa;
+use crate::symbolic::Sfa;
+use crate::{classLabel, Glushkov};
+
+// Escapes a label for use inside a quoted DOT string
+fn dot_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+// Common header: left to right layout and an arrow into the initial state 0
+fn header(name: &str) -> String {
+    format!("digraph {} {{\n    rankdir=LR;\n    node [shape=circle];\n    init [shape=point];\n    init -> 0;\n", name)
+}
+
+impl Glushkov {
+    // Renders the NFA with state 0 as initial, accepting states double-circled and
+    // every edge labelled with the label of the position it enters
+    pub fn to_dot(&self) -> String {
+        let mut res = header("glushkov");
+        for state in 0..self.no_of_states {
+            if self.is_final(state) {
+                res.push_str(&format!("    {} [shape=doublecircle];\n", state));
+            }
+        }
+        for (i, next) in self.nfa.iter().enumerate() {
+            let mut next = next.clone();
+            next.sort();
+            for j in next {
+                res.push_str(&format!("    {} -> {} [label=\"{}\"];\n", i, j, dot_escape(&self.label(j))));
+            }
+        }
+        res.push_str("}\n");
+        res
+    }
+}
+
+impl Dfa {
+    // Renders the DFA, merging the bytes leading to the same state into one class label;
+    // the dead state and the edges into it are left out
+    pub fn to_dot(&self) -> String {
+        let mut res = header("dfa");
+        for state in 0..self.no_of_states() {
+            if self.finals[state] {
+                res.push_str(&format!("    {} [shape=doublecircle];\n", state));
+            }
+        }
+        for state in 0..self.no_of_states() {
+            if self.is_dead(state) && state != 0 {
+                continue;
+            }
+            let mut targets: Vec<usize> = self.trans[state].clone();
+            targets.sort();
+            targets.dedup();
+            for t in targets {
+                if self.is_dead(t) && t != 0 {
+                    continue;
+                }
+                let charvec: Vec<bool> = (0..=255u8).map(|byte| self.next(state, byte) == t).collect();
+                res.push_str(&format!("    {} -> {} [label=\"{}\"];\n", state, t, dot_escape(&classLabel(&charvec))));
+            }
+        }
+        res.push_str("}\n");
+        res
+    }
+}
+
+impl Nfa {
+    // Renders the automaton with every state described by what it stands for in its
+    // construction, the transitions labelled by their classes and the ε-transitions dashed
+    pub fn to_dot(&self) -> String {
+        let mut res = header("nfa");
+        for (state, label) in self.labels.iter().enumerate() {
+            let shape = if self.finals[state] { "doublecircle" } else { "circle" };
+            res.push_str(&format!("    {} [shape={}, label=\"{}\"];\n", state, shape, dot_escape(label)));
+        }
+        for (state, out) in self.edges.iter().enumerate() {
+            for (class, t) in out {
+                res.push_str(&format!("    {} -> {} [label=\"{}\"];\n", state, t, dot_escape(&classLabel(class))));
+            }
+        }
+        for (state, out) in self.eps.iter().enumerate() {
+            for t in out {
+                res.push_str(&format!("    {} -> {} [label=\"ε\", style=dashed];\n", state, t));
+            }
+        }
+        res.push_str("}\n");
+        res
+    }
+}
+
+impl Sfa {
+    // Renders the automaton with every transition labelled by its predicate
+    pub fn to_dot(&self) -> String {
+        let mut res = header("sfa");
+        for state in 0..self.no_of_states() {
+            if self.finals[state] {
+                res.push_str(&format!("    {} [shape=doublecircle];\n", state));
+            }
+        }
+        for (state, out) in self.edges.iter().enumerate() {
+            for (p, t) in out {
+                res.push_str(&format!("    {} -> {} [label=\"{}\"];\n", state, t, dot_escape(&classLabel(&p.to_class()))));
+            }
+        }
+        res.push_str("}\n");
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::compile;
+    use crate::dfa;
+
+    #[test]
+    fn glushkov_snapshot() {
+        assert_eq!(compile("ab*").unwrap().to_dot(), "digraph glushkov {\n    rankdir=LR;\n    node [shape=circle];\n    \
+            init [shape=point];\n    init -> 0;\n    1 [shape=doublecircle];\n    2 [shape=doublecircle];\n    \
+            0 -> 1 [label=\"a\"];\n    1 -> 2 [label=\"b\"];\n    2 -> 2 [label=\"b\"];\n}\n");
+    }
+
+    // The dead state and the edges into it are left out
+    #[test]
+    fn dfa_snapshot() {
+        assert_eq!(dfa::compile("ab*").unwrap().minimise().to_dot(), "digraph dfa {\n    rankdir=LR;\n    node [shape=circle];\n    \
+            init [shape=point];\n    init -> 0;\n    2 [shape=doublecircle];\n    \
+            0 -> 2 [label=\"a\"];\n    2 -> 2 [label=\"b\"];\n}\n");
+    }
+
+    #[test]
+    fn labels_are_escaped() {
+        let dot = compile("[\\x22\\x5c]").unwrap().to_dot();
+        assert!(dot.contains("0 -> 1 [label=\"[\\\\x22\\\\x5c]\"];"), "{}", dot);
+    }
+}
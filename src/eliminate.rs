@@ -0,0 +1,119 @@
+// Conversion of automata back to expressions by state elimination, the expressions on the
+// edges being kept small by the smart constructors of simplify
+
+use std::rc::Rc;
+
+use crate::automaton::Nfa;
+use crate::dfa::Dfa;
+use crate::simplify::{mkClass, mkConcat, mkOr, mkStar};
+use crate::Regex;
+use crate::Regex::{Empty, Eps};
+
+// Adds an expression to an edge of the generalised automaton, as an alternative to the one already there
+fn addEdge(edges: &mut [Vec<Option<Rc<Regex>>>], p: usize, q: usize, r: Rc<Regex>) {
+    edges[p][q] = Some(match &edges[p][q] {
+        Some(old) => mkOr(old, &r),
+        None => r
+    });
+}
+
+impl Nfa {
+    // Expression matching the language of the automaton. A new initial state n and a new final
+    // state n+1 are linked by ε to state 0 and from the final states; the states of the automaton
+    // are then removed one by one, the one with the fewest paths through it first, each path
+    // p -> q -> r being replaced by the edge p -> r labelled pq (qq)* qr
+    pub fn to_regex(&self) -> Rc<Regex> {
+        let n = self.no_of_states();
+        let mut edges: Vec<Vec<Option<Rc<Regex>>>> = vec![vec![None; n + 2]; n + 2];
+        for (p, out) in self.edges.iter().enumerate() {
+            for (class, q) in out {
+                addEdge(&mut edges, p, *q, mkClass(class.clone()));
+            }
+        }
+        for (p, out) in self.eps.iter().enumerate() {
+            for q in out {
+                addEdge(&mut edges, p, *q, Rc::new(Eps()));
+            }
+        }
+        addEdge(&mut edges, n, 0, Rc::new(Eps()));
+        for p in 0..n {
+            if self.finals[p] {
+                addEdge(&mut edges, p, n + 1, Rc::new(Eps()));
+            }
+        }
+        let mut remaining: Vec<usize> = (0..n).collect();
+        while !remaining.is_empty() {
+            let paths = |q: usize| {
+                let ins = (0..n + 2).filter(|p| *p != q && edges[*p][q].is_some()).count();
+                let outs = (0..n + 2).filter(|r| *r != q && edges[q][*r].is_some()).count();
+                ins * outs
+            };
+            let (i, _) = remaining.iter().enumerate().min_by_key(|(_, q)| paths(**q)).unwrap();
+            let q = remaining.remove(i);
+            let lp = match &edges[q][q] {
+                Some(r) => mkStar(r),
+                None => Rc::new(Eps())
+            };
+            for p in 0..n + 2 {
+                if p == q {
+                    continue;
+                }
+                let pq = match &edges[p][q] {
+                    Some(r) => r.clone(),
+                    None => continue
+                };
+                for r in 0..n + 2 {
+                    if r == q {
+                        continue;
+                    }
+                    if let Some(qr) = edges[q][r].clone() {
+                        addEdge(&mut edges, p, r, mkConcat(&pq, &mkConcat(&lp, &qr)));
+                    }
+                }
+            }
+            edges[q] = vec![None; n + 2];
+            for row in edges.iter_mut() {
+                row[q] = None;
+            }
+        }
+        edges[n][n + 1].clone().unwrap_or_else(|| Rc::new(Empty()))
+    }
+}
+
+impl Dfa {
+    // Expression matching the language of the DFA, through its automaton without dead states
+    pub fn to_regex(&self) -> Rc<Regex> {
+        self.to_nfa().to_regex()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::automaton::{construct, CONSTRUCTIONS};
+    use crate::equiv::{equivalentDfa, Comparison};
+    use crate::{dfa, parse};
+
+    // The expression back from every automaton, and its printed form parsed again, match the same strings
+    #[test]
+    fn same_language() {
+        for pattern in ["(a|b)*abb", "a?b?c?", "(ab|ba)*c", "[ab]{2,3}", "a(b|c)*|ca", "(()|a)b*", "((a|b)c)+", "\\x00.[^a]"] {
+            let d = dfa::compile(pattern).unwrap();
+            let mut exprs = vec![d.to_regex(), d.minimise().to_regex()];
+            for (_, c) in CONSTRUCTIONS {
+                exprs.push(construct(pattern, c).unwrap().to_regex());
+            }
+            for r in exprs {
+                assert!(matches!(equivalentDfa(&dfa::fromRegex(&r), &d), Comparison::Holds), "{} gave {}", pattern, r);
+                let (reparsed, _) = parse(&r.to_string()).unwrap();
+                assert!(matches!(equivalentDfa(&dfa::fromRegex(&reparsed), &d), Comparison::Holds), "{} gave {}", pattern, r);
+            }
+        }
+    }
+
+    #[test]
+    fn small_results() {
+        assert_eq!(dfa::compile("a*a*").unwrap().minimise().to_regex().to_string(), "a*");
+        assert_eq!(dfa::compile("a&b").unwrap().minimise().to_regex().to_string(), "[^\\x00-\\xff]");
+        assert_eq!(dfa::compile("ab|ac").unwrap().minimise().to_regex().to_string(), "a[b-c]");
+    }
+}
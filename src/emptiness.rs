@@ -0,0 +1,120 @@
+// Emptiness check and shortest accepted string, by breadth-first search from the initial state
+
+use std::collections::VecDeque;
+
+use crate::classes::representatives;
+use crate::dfa::Dfa;
+use crate::Glushkov;
+
+// Follows the predecessor links of a breadth-first search back to the initial state 0
+fn walk_back(prev: &[Option<(usize, u8)>], state: usize) -> Vec<u8> {
+    let mut res: Vec<u8> = Vec::new();
+    let mut curr = state;
+    while let Some((from, byte)) = prev[curr] {
+        res.push(byte);
+        curr = from;
+    }
+    res.reverse();
+    res
+}
+
+impl Glushkov {
+    // Shortest string taking state 0 to a state of the D set (or the empty string for nullable
+    // expressions), reading the smallest byte of each position entered; None when nothing matches
+    pub fn shortest_match_example(&self) -> Option<Vec<u8>> {
+        if self.nullable {
+            return Some(Vec::new());
+        }
+        let n = self.no_of_states as usize;
+        let mut prev: Vec<Option<(usize, u8)>> = vec![None; n];
+        let mut seen: Vec<bool> = vec![false; n];
+        let mut queue: VecDeque<usize> = VecDeque::new();
+        seen[0] = true;
+        queue.push_back(0);
+        while let Some(curr) = queue.pop_front() {
+            if self.is_final(curr as u8) {
+                return Some(walk_back(&prev, curr));
+            }
+            for j in &self.nfa[curr] {
+                let j = *j as usize;
+                // A position labelled by a class with no byte in it can never be entered
+                let byte = match self.state_letter[j - 1].iter().position(|x| *x) {
+                    Some(byte) => byte as u8,
+                    None => continue
+                };
+                if !seen[j] {
+                    seen[j] = true;
+                    prev[j] = Some((curr, byte));
+                    queue.push_back(j);
+                }
+            }
+        }
+        None
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shortest_match_example().is_none()
+    }
+}
+
+impl Dfa {
+    // Same search on a DFA, for patterns using & and ~
+    pub fn shortest_match_example(&self) -> Option<Vec<u8>> {
+        let n = self.no_of_states();
+        let bytes = representatives(&self.classes);
+        let mut prev: Vec<Option<(usize, u8)>> = vec![None; n];
+        let mut seen: Vec<bool> = vec![false; n];
+        let mut queue: VecDeque<usize> = VecDeque::new();
+        seen[0] = true;
+        queue.push_back(0);
+        while let Some(curr) = queue.pop_front() {
+            if self.finals[curr] {
+                return Some(walk_back(&prev, curr));
+            }
+            for &byte in &bytes {
+                let next = self.next(curr, byte);
+                if !seen[next] {
+                    seen[next] = true;
+                    prev[next] = Some((curr, byte));
+                    queue.push_back(next);
+                }
+            }
+        }
+        None
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shortest_match_example().is_none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::all_strings;
+    use crate::{compile, dfa};
+
+    #[test]
+    fn shortest_examples() {
+        let strings = all_strings(b"abc", 6);
+        for pattern in ["a", "ab*c", "(a|b)*abb", "c?", "(ab|c)+b", "a{3}|b{2}", "[bc]a|cc"] {
+            let g = compile(pattern).unwrap();
+            let w = g.shortest_match_example().unwrap();
+            assert!(g.accepts(&w), "{}", pattern);
+            let shortest = strings.iter().find(|s| g.accepts(s)).unwrap();
+            assert_eq!(w.len(), shortest.len(), "{}", pattern);
+            let d = dfa::compile(pattern).unwrap();
+            assert_eq!(d.shortest_match_example().map(|w| w.len()), Some(shortest.len()), "{}", pattern);
+        }
+    }
+
+    #[test]
+    fn empty_languages() {
+        assert!(compile("[^\\x00-\\xff]").unwrap().is_empty());
+        assert!(compile("a[^\\x00-\\xff]*b[^\\x00-\\xff]").unwrap().is_empty());
+        assert!(!compile("a[^\\x00-\\xff]*b").unwrap().is_empty());
+        assert!(dfa::compile("a&b").unwrap().is_empty());
+        assert!(dfa::compile("~(.*)").unwrap().is_empty());
+        assert!(!dfa::compile("(a|b)*&~(a*)").unwrap().is_empty());
+        assert_eq!(dfa::compile("(a|b)*&~(a*)").unwrap().shortest_match_example(), Some(b"b".to_vec()));
+    }
+}
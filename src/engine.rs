@@ -0,0 +1,90 @@
+// Choice of the matching engine behind a compiled pattern
+
+use crate::ahocorasick::{literal_set, AhoCorasick};
+use crate::derivative::Derivatives;
+use crate::dfa::{self, Dfa};
+use crate::simplify::simplify;
+use crate::{isExtended, parse, buildSimplified, Glushkov, Rule};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Engine {
+    // Glushkov automaton simulated on sets of positions, its DFA for patterns with & and ~, or
+    // an Aho–Corasick automaton for finite sets of literals
+    Glushkov,
+    // Brzozowski derivatives, building the derivative DFA lazily
+    Derivative
+}
+
+pub enum Matcher {
+    Glushkov(Glushkov),
+    Dfa(Dfa),
+    Derivative(Derivatives),
+    AhoCorasick(AhoCorasick)
+}
+
+impl Matcher {
+    pub fn new(regex_input: &str, engine: Engine) -> Result<Matcher, pest::error::Error<Rule>> {
+        let (x, all_state_labels) = parse(regex_input)?;
+        // The literal sets are looked for in the simplified AST, the one Searcher sees in g.ast
+        let y = simplify(&x);
+        if engine == Engine::Glushkov {
+            if let Some(keywords) = literal_set(&y) {
+                return Ok(Matcher::AhoCorasick(AhoCorasick::new(&keywords)));
+            }
+        }
+        Ok(match engine {
+            Engine::Derivative => Matcher::Derivative(Derivatives::new(&x)),
+            Engine::Glushkov if isExtended(&y) => Matcher::Dfa(dfa::fromRegex(&y)),
+            Engine::Glushkov => Matcher::Glushkov(buildSimplified(x, all_state_labels))
+        })
+    }
+
+    // Checks whether the whole string is matched
+    pub fn is_match(&mut self, s: &[u8]) -> bool {
+        match self {
+            Matcher::Glushkov(g) => g.accepts(s),
+            Matcher::Dfa(d) => d.accepts(s),
+            Matcher::Derivative(d) => d.is_match(s),
+            Matcher::AhoCorasick(a) => a.is_match(s)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::all_strings;
+
+    // Both engines accept the same strings, whichever matcher the Glushkov engine picks
+    #[test]
+    fn engines_agree() {
+        let strings = all_strings(b"abc", 5);
+        for pattern in ["(a|b)*abb", "ab|abc|c", "a&~(b)", "(~(a*))c", "[ab]+c?", "abc|b"] {
+            let mut m1 = Matcher::new(pattern, Engine::Glushkov).unwrap();
+            let mut m2 = Matcher::new(pattern, Engine::Derivative).unwrap();
+            for s in &strings {
+                assert_eq!(m1.is_match(s), m2.is_match(s), "{} on {:?}", pattern, s);
+            }
+        }
+    }
+
+    #[test]
+    fn matcher_choice() {
+        assert!(matches!(Matcher::new("ab|c", Engine::Glushkov).unwrap(), Matcher::AhoCorasick(_)));
+        assert!(matches!(Matcher::new("a&b", Engine::Glushkov).unwrap(), Matcher::Dfa(_)));
+        assert!(matches!(Matcher::new("a*", Engine::Glushkov).unwrap(), Matcher::Glushkov(_)));
+        assert!(matches!(Matcher::new("ab|c", Engine::Derivative).unwrap(), Matcher::Derivative(_)));
+    }
+
+    // The matcher and the searcher take the Aho–Corasick path for the same patterns, decided on
+    // the simplified AST
+    #[test]
+    fn same_literal_sets_as_the_searcher() {
+        for pattern in ["ab|c", "(ab|c)()", "a*", "(a|b){2}", "(a|())b", "a{2}|a{2}"] {
+            let keywords = matches!(Matcher::new(pattern, Engine::Glushkov).unwrap(), Matcher::AhoCorasick(_));
+            assert_eq!(keywords, literal_set(&crate::compile(pattern).unwrap().ast).is_some(), "{}", pattern);
+        }
+        assert!(matches!(Matcher::new("~~(ab|c)", Engine::Glushkov).unwrap(), Matcher::AhoCorasick(_)));
+        assert!(matches!(Matcher::new("a&a", Engine::Glushkov).unwrap(), Matcher::AhoCorasick(_)));
+    }
+}
@@ -0,0 +1,166 @@
+// Enumeration of the strings matched by a pattern in length-lexicographic (shortlex) order
+
+use crate::Glushkov;
+
+// Iterator over the matched strings, shortest first and in byte order within a length. It walks
+// the Glushkov automaton depth first, one length at a time, keeping sets of positions so that a
+// string reached by several paths is produced once, and only entering sets that can still
+// reach the D set in exactly the number of bytes left
+pub struct Strings<'a> {
+    g: &'a Glushkov,
+    max_len: Option<usize>,
+    max_count: Option<usize>,
+    produced: usize,
+    len: usize,
+    // Position set and next byte to try for every prefix being extended
+    stack: Vec<(Vec<u8>, u16)>,
+    prefix: Vec<u8>,
+    // can[r][i] : position i reaches an accepting position reading exactly r bytes
+    can: Vec<Vec<bool>>,
+    done: bool
+}
+
+impl Glushkov {
+    // Enumerates the matched strings, optionally up to a length and a number of strings
+    pub fn enumerate(&self, max_len: Option<usize>, max_count: Option<usize>) -> Strings<'_> {
+        let n = self.no_of_states as usize;
+        let can0: Vec<bool> = (0..n).map(|i| self.is_final(i as u8)).collect();
+        Strings {
+            g: self,
+            max_len,
+            max_count,
+            produced: 0,
+            len: 0,
+            stack: Vec::new(),
+            prefix: Vec::new(),
+            can: vec![can0],
+            done: false
+        }
+    }
+}
+
+impl<'a> Strings<'a> {
+    // Extends the table of can up to r bytes
+    fn compute_can(&mut self, r: usize) {
+        let n = self.g.no_of_states as usize;
+        while self.can.len() <= r {
+            let last = self.can.last().unwrap();
+            let mut row: Vec<bool> = vec![false; n];
+            for (i, x) in row.iter_mut().enumerate() {
+                *x = self.g.nfa[i].iter().any(|j| {
+                    last[*j as usize] && self.g.state_letter[(j-1) as usize].iter().any(|b| *b)
+                });
+            }
+            self.can.push(row);
+        }
+    }
+
+    fn can_reach(&mut self, states: &[u8], r: usize) -> bool {
+        self.compute_can(r);
+        states.iter().any(|x| self.can[r][*x as usize])
+    }
+
+    // Without a string of some length between n and 2n-1 for n states, the pumping lemma
+    // says no string has length n or more, so the language is finite and exhausted
+    fn exhausted(&mut self) -> bool {
+        let n = self.g.no_of_states as usize;
+        if self.len < 2 * n {
+            return false;
+        }
+        self.compute_can(2 * n);
+        !(n..2 * n).any(|l| self.can[l][0])
+    }
+}
+
+impl<'a> Iterator for Strings<'a> {
+    type Item = Vec<u8>;
+
+    fn next(&mut self) -> Option<Vec<u8>> {
+        if self.max_count.is_some_and(|c| self.produced >= c) {
+            self.done = true;
+        }
+        while !self.done {
+            if self.stack.is_empty() {
+                // Start on the next length that has at least one string
+                if self.max_len.is_some_and(|m| self.len > m) || self.exhausted() {
+                    self.done = true;
+                    break;
+                }
+                if !self.can_reach(&[0], self.len) {
+                    self.len += 1;
+                    continue;
+                }
+                self.stack.push((vec![0], 0));
+            }
+            let depth = self.prefix.len();
+            if depth == self.len {
+                // A complete string, the pruning guarantees it is accepted
+                let res = self.prefix.clone();
+                self.stack.pop();
+                self.prefix.pop();
+                if self.stack.is_empty() {
+                    self.len += 1;
+                }
+                self.produced += 1;
+                return Some(res);
+            }
+            let (states, from) = self.stack.last().unwrap().clone();
+            let mut found: Option<(Vec<u8>, u16)> = None;
+            for byte in from..256 {
+                let next = self.g.step(&states, byte as u8);
+                if !next.is_empty() && self.can_reach(&next, self.len - depth - 1) {
+                    found = Some((next, byte));
+                    break;
+                }
+            }
+            match found {
+                Some((next, byte)) => {
+                    self.stack.last_mut().unwrap().1 = byte + 1;
+                    self.prefix.push(byte as u8);
+                    self.stack.push((next, 0));
+                },
+                None => {
+                    self.stack.pop();
+                    self.prefix.pop();
+                    if self.stack.is_empty() {
+                        self.len += 1;
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::compile;
+    use crate::tests::all_strings;
+
+    // Shortlex order and no duplicates, against filtering all short strings
+    #[test]
+    fn matches_brute_force() {
+        let strings = all_strings(b"abc", 5);
+        for pattern in ["a*", "(a|b)*abb", "(a|ab)(c|bca)?", "a?b?c?", "(ab|a)(ba|b)*", "c{2,}|b"] {
+            let g = compile(pattern).unwrap();
+            let expected: Vec<Vec<u8>> = strings.iter().filter(|s| g.accepts(s)).cloned().collect();
+            let got: Vec<Vec<u8>> = g.enumerate(Some(5), None).collect();
+            assert_eq!(got, expected, "{}", pattern);
+        }
+    }
+
+    #[test]
+    fn finite_languages_end() {
+        let g = compile("a|bc|()|a{2,3}").unwrap();
+        let got: Vec<Vec<u8>> = g.enumerate(None, None).collect();
+        assert_eq!(got, vec![b"".to_vec(), b"a".to_vec(), b"aa".to_vec(), b"bc".to_vec(), b"aaa".to_vec()]);
+        assert_eq!(compile("[^\\x00-\\xff]").unwrap().enumerate(None, None).count(), 0);
+    }
+
+    #[test]
+    fn count_limit() {
+        let g = compile("[ab]*").unwrap();
+        let got: Vec<Vec<u8>> = g.enumerate(None, Some(4)).collect();
+        assert_eq!(got, vec![b"".to_vec(), b"a".to_vec(), b"b".to_vec(), b"aa".to_vec()]);
+    }
+}
@@ -0,0 +1,164 @@
+// Language equivalence and inclusion of patterns, with shortest distinguishing strings
+
+use std::collections::VecDeque;
+
+use crate::classes::{refine, representatives};
+use crate::dfa::{self, Dfa};
+use crate::Rule;
+
+// Outcome of a comparison, with a shortest string telling the two patterns apart when it fails
+#[derive(Debug)]
+pub enum Comparison {
+    Holds,
+    Fails(Vec<u8>)
+}
+
+fn find(parent: &mut [usize], x: usize) -> usize {
+    let mut root = x;
+    while parent[root] != root {
+        root = parent[root];
+    }
+    // Path compression
+    let mut curr = x;
+    while parent[curr] != root {
+        let next = parent[curr];
+        parent[curr] = root;
+        curr = next;
+    }
+    root
+}
+
+// Hopcroft–Karp: merges the classes of paired states, which the states of the second DFA join
+// shifted by the size of the first, and fails as soon as an accepting state is paired with a non-accepting one
+fn hopcroftKarp(d1: &Dfa, d2: &Dfa) -> bool {
+    let n1 = d1.no_of_states();
+    let bytes = representatives(&refine(&d1.classes, &d2.classes));
+    let mut parent: Vec<usize> = (0..n1 + d2.no_of_states()).collect();
+    let mut queue: VecDeque<(usize, usize)> = VecDeque::new();
+    parent[n1] = 0;
+    queue.push_back((0, 0));
+    while let Some((p, q)) = queue.pop_front() {
+        if d1.finals[p] != d2.finals[q] {
+            return false;
+        }
+        for &byte in &bytes {
+            let p1 = d1.next(p, byte);
+            let q1 = d2.next(q, byte);
+            let r1 = find(&mut parent, p1);
+            let r2 = find(&mut parent, n1 + q1);
+            if r1 != r2 {
+                parent[r1] = r2;
+                queue.push_back((p1, q1));
+            }
+        }
+    }
+    true
+}
+
+// Breadth-first search over pairs of states for a shortest string leading to a pair where bad holds
+fn shortestPair(d1: &Dfa, d2: &Dfa, bad: fn(bool, bool) -> bool) -> Option<Vec<u8>> {
+    let n2 = d2.no_of_states();
+    let bytes = representatives(&refine(&d1.classes, &d2.classes));
+    let mut prev: Vec<Option<(usize, u8)>> = vec![None; d1.no_of_states() * n2];
+    let mut seen: Vec<bool> = vec![false; d1.no_of_states() * n2];
+    let mut queue: VecDeque<(usize, usize)> = VecDeque::new();
+    seen[0] = true;
+    queue.push_back((0, 0));
+    while let Some((p, q)) = queue.pop_front() {
+        if bad(d1.finals[p], d2.finals[q]) {
+            let mut res: Vec<u8> = Vec::new();
+            let mut curr = p * n2 + q;
+            while let Some((from, byte)) = prev[curr] {
+                res.push(byte);
+                curr = from;
+            }
+            res.reverse();
+            return Some(res);
+        }
+        for &byte in &bytes {
+            let p1 = d1.next(p, byte);
+            let q1 = d2.next(q, byte);
+            if !seen[p1 * n2 + q1] {
+                seen[p1 * n2 + q1] = true;
+                prev[p1 * n2 + q1] = Some((p * n2 + q, byte));
+                queue.push_back((p1, q1));
+            }
+        }
+    }
+    None
+}
+
+// Checks that two DFAs accept the same strings; union-find decides, and only when
+// they differ is the product searched for a shortest string accepted by exactly one of them
+pub fn equivalentDfa(d1: &Dfa, d2: &Dfa) -> Comparison {
+    if hopcroftKarp(d1, d2) {
+        Comparison::Holds
+    } else {
+        Comparison::Fails(shortestPair(d1, d2, |a, b| a != b).unwrap())
+    }
+}
+
+// Checks that every string accepted by d1 is accepted by d2, else gives a shortest one that is not
+pub fn isSubsetDfa(d1: &Dfa, d2: &Dfa) -> Comparison {
+    match shortestPair(d1, d2, |a, b| a && !b) {
+        None => Comparison::Holds,
+        Some(w) => Comparison::Fails(w)
+    }
+}
+
+pub fn equivalent(a: &str, b: &str) -> Result<Comparison, pest::error::Error<Rule>> {
+    Ok(equivalentDfa(&dfa::compile(a)?, &dfa::compile(b)?))
+}
+
+pub fn is_subset(a: &str, b: &str) -> Result<Comparison, pest::error::Error<Rule>> {
+    Ok(isSubsetDfa(&dfa::compile(a)?, &dfa::compile(b)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::all_strings;
+
+    fn differ(a: &Dfa, b: &Dfa, s: &[u8]) -> bool {
+        a.accepts(s) != b.accepts(s)
+    }
+
+    #[test]
+    fn known_pairs() {
+        assert!(matches!(equivalent("(a|b)*", "(a*b*)*").unwrap(), Comparison::Holds));
+        assert!(matches!(equivalent("a(ba)*", "(ab)*a").unwrap(), Comparison::Holds));
+        assert!(matches!(equivalent("~(a*)", ".*[^a].*").unwrap(), Comparison::Holds));
+        assert!(matches!(equivalent("a*", "a*b?").unwrap(), Comparison::Fails(w) if w == b"b"));
+        assert!(matches!(is_subset("ab", "a*b*").unwrap(), Comparison::Holds));
+        assert!(matches!(is_subset("a*b*", "ab").unwrap(), Comparison::Fails(w) if w.is_empty()));
+    }
+
+    // Against brute force over short strings: no string tells equivalent patterns apart, and the
+    // witness of a failed comparison does while no shorter string does
+    #[test]
+    fn witnesses_are_shortest() {
+        let patterns = ["a*", "(a|b)*", "a*b*", "(ab)*", "a(ba)*b", "(a|b)*abb", "b?a*", "(aa)*|a", "a&b", "~(ab)"];
+        let strings = all_strings(b"ab", 7);
+        for p1 in patterns {
+            for p2 in patterns {
+                let d1 = dfa::compile(p1).unwrap();
+                let d2 = dfa::compile(p2).unwrap();
+                match equivalentDfa(&d1, &d2) {
+                    Comparison::Holds => assert!(!strings.iter().any(|s| differ(&d1, &d2, s)), "{} {}", p1, p2),
+                    Comparison::Fails(w) => {
+                        assert!(differ(&d1, &d2, &w), "{} {}", p1, p2);
+                        assert!(!strings.iter().any(|s| s.len() < w.len() && differ(&d1, &d2, s)), "{} {}", p1, p2);
+                    }
+                }
+                let included = |s: &Vec<u8>| !d1.accepts(s) || d2.accepts(s);
+                match isSubsetDfa(&d1, &d2) {
+                    Comparison::Holds => assert!(strings.iter().all(included), "{} {}", p1, p2),
+                    Comparison::Fails(w) => {
+                        assert!(!included(&w), "{} {}", p1, p2);
+                        assert!(strings.iter().all(|s| s.len() >= w.len() || included(s)), "{} {}", p1, p2);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,148 @@
+// Step-by-step explanation of how the Glushkov automaton reads an input string
+
+use serde::Serialize;
+
+use crate::{compile_unsimplified, json, Glushkov, Rule};
+
+// Active positions after reading one byte of the input
+#[derive(Serialize)]
+pub struct Step {
+    #[serde(rename = "index")]
+    pub idx: usize,
+    #[serde(serialize_with = "json::shown_byte")]
+    pub byte: u8,
+    #[serde(serialize_with = "json::labelled_positions")]
+    pub active: Vec<(u8, String)>
+}
+
+// Trace of a whole match: the initial position set, one step per byte read, and the verdict
+#[derive(Serialize)]
+pub struct Explanation {
+    pub pattern: String,
+    pub input: String,
+    #[serde(serialize_with = "json::labelled_positions")]
+    pub initial: Vec<(u8, String)>,
+    pub steps: Vec<Step>,
+    pub accepted: bool
+}
+
+// Annotates each active position with its label
+fn annotate(g: &Glushkov, states: &[u8]) -> Vec<(u8, String)> {
+    states.iter().map(|x| (*x, g.label(*x))).collect()
+}
+
+// Runs the Glushkov automaton of the pattern as written on the input, recording the active
+// position set after every byte
+pub fn explain(pattern: &str, input: &str) -> Result<Explanation, pest::error::Error<Rule>> {
+    let g = compile_unsimplified(pattern)?;
+    Ok(explain_compiled(&g, pattern, input))
+}
+
+// Same as explain, for an already compiled pattern
+pub fn explain_compiled(g: &Glushkov, pattern: &str, input: &str) -> Explanation {
+    let mut curr: Vec<u8> = vec![0];
+    let mut steps: Vec<Step> = Vec::new();
+    for (idx, byte) in input.bytes().enumerate() {
+        curr = g.step(&curr, byte);
+        steps.push(Step {
+            idx,
+            byte,
+            active: annotate(g, &curr)
+        });
+        // No position can become active again once the set is empty
+        if curr.is_empty() {
+            break;
+        }
+    }
+    let accepted = steps.len() == input.len() && curr.iter().any(|x| g.is_final(*x));
+    Explanation {
+        pattern: pattern.to_string(),
+        input: input.to_string(),
+        initial: annotate(g, &[0]),
+        steps,
+        accepted
+    }
+}
+
+// Shows a byte as a character when printable, as an escape otherwise
+pub fn show_byte(byte: u8) -> String {
+    if byte.is_ascii_graphic() || byte == b' ' {
+        (byte as char).to_string()
+    } else {
+        format!("\\x{:02x}", byte)
+    }
+}
+
+fn positions_text(active: &[(u8, String)]) -> String {
+    if active.is_empty() {
+        return "(none)".to_string();
+    }
+    let items: Vec<String> = active.iter().map(|(x, label)| format!("{}:{}", x, label)).collect();
+    items.join(" ")
+}
+
+impl Explanation {
+    // Renders the trace as a plain text table, one row per byte read
+    pub fn to_table(&self) -> String {
+        let mut res = String::new();
+        res.push_str(&format!("Pattern: {}\nInput: {}\n", self.pattern, self.input));
+        res.push_str(&format!("{:<6} {:<6} {}\n", "Index", "Byte", "Active positions"));
+        res.push_str(&format!("{:<6} {:<6} {}\n", "-", "-", positions_text(&self.initial)));
+        for step in &self.steps {
+            res.push_str(&format!("{:<6} {:<6} {}\n", step.idx, show_byte(step.byte), positions_text(&step.active)));
+        }
+        if self.steps.len() < self.input.len() {
+            res.push_str("No active positions left, remaining input is not read\n");
+        }
+        res.push_str(if self.accepted { "Accepted\n" } else { "Rejected\n" });
+        res
+    }
+
+    // Renders the trace as a JSON object
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_snapshot() {
+        let e = explain("a[bc]", "ab").unwrap();
+        assert_eq!(e.to_json(), concat!(
+            r#"{"pattern":"a[bc]","input":"ab","initial":[{"position":0,"label":"start"}],"#,
+            r#""steps":[{"index":0,"byte":"a","active":[{"position":1,"label":"a"}]},"#,
+            r#"{"index":1,"byte":"b","active":[{"position":2,"label":"[bc]"}]}],"accepted":true}"#
+        ));
+    }
+
+    #[test]
+    fn json_escapes_bytes() {
+        let e = explain("a", "\n").unwrap();
+        assert_eq!(e.to_json(), concat!(
+            r#"{"pattern":"a","input":"\n","initial":[{"position":0,"label":"start"}],"#,
+            r#""steps":[{"index":0,"byte":"\\x0a","active":[]}],"accepted":false}"#
+        ));
+    }
+
+    #[test]
+    fn table_snapshot() {
+        let e = explain("a[bc]", "ax").unwrap();
+        assert_eq!(e.to_table(), "Pattern: a[bc]\nInput: ax\n\
+            Index  Byte   Active positions\n\
+            -      -      0:start\n\
+            0      a      1:a\n\
+            1      x      (none)\n\
+            Rejected\n");
+    }
+
+    #[test]
+    fn stops_when_no_position_is_active() {
+        let e = explain("ab", "xab").unwrap();
+        assert_eq!(e.steps.len(), 1);
+        assert!(!e.accepted);
+        assert!(e.to_table().contains("remaining input is not read"));
+    }
+}
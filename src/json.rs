@@ -0,0 +1,178 @@
+// JSON export and import of the AST and of the Glushkov automaton
+
+use std::collections::HashSet;
+use std::hash::Hash;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{classRanges, Glushkov};
+use crate::explain::show_byte;
+
+// Writes a set in increasing order so that exports of the same pattern are identical
+pub fn sorted_set<T, S>(set: &HashSet<T>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: Ord + Hash + Serialize,
+    S: Serializer
+{
+    let mut items: Vec<&T> = set.iter().collect();
+    items.sort();
+    items.serialize(serializer)
+}
+
+// Writes a byte as the character shown by the explanation table
+pub fn shown_byte<S: Serializer>(byte: &u8, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&show_byte(*byte))
+}
+
+#[derive(Serialize)]
+struct Position<'a> {
+    position: u8,
+    label: &'a str
+}
+
+// Writes labelled positions as {"position": .., "label": ..} objects
+pub fn labelled_positions<S: Serializer>(active: &[(u8, String)], serializer: S) -> Result<S::Ok, S::Error> {
+    let items: Vec<Position> = active.iter().map(|(x, label)| Position { position: *x, label }).collect();
+    items.serialize(serializer)
+}
+
+// Writes a character class as its list of inclusive byte ranges instead of 256 booleans
+pub mod class_ranges {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(charvec: &[bool], serializer: S) -> Result<S::Ok, S::Error> {
+        classRanges(charvec).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<bool>, D::Error> {
+        let ranges: Vec<(u8, u8)> = Vec::deserialize(deserializer)?;
+        let mut charvec: Vec<bool> = vec![false; 256];
+        for (a, b) in ranges {
+            for i in a..=b {
+                charvec[i as usize] = true;
+            }
+        }
+        Ok(charvec)
+    }
+}
+
+// Same as class_ranges, for the list of classes labelling the positions
+pub mod class_list {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(classes: &[Vec<bool>], serializer: S) -> Result<S::Ok, S::Error> {
+        let ranges: Vec<Vec<(u8, u8)>> = classes.iter().map(|x| classRanges(x)).collect();
+        ranges.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Vec<bool>>, D::Error> {
+        let ranges: Vec<Vec<(u8, u8)>> = Vec::deserialize(deserializer)?;
+        let mut classes: Vec<Vec<bool>> = Vec::new();
+        for class in ranges {
+            let mut charvec: Vec<bool> = vec![false; 256];
+            for (a, b) in class {
+                for i in a..=b {
+                    charvec[i as usize] = true;
+                }
+            }
+            classes.push(charvec);
+        }
+        Ok(classes)
+    }
+}
+
+impl Glushkov {
+    // Exports the AST, the augmented AST, the P, D and F sets and the transition table
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+
+    // Imports an automaton exported by to_json, rejecting one whose tables do not fit together
+    pub fn from_json(s: &str) -> Result<Glushkov, serde_json::Error> {
+        let g: Glushkov = serde_json::from_str(s)?;
+        g.check().map_err(serde::de::Error::custom)?;
+        Ok(g)
+    }
+
+    // Checks that every table has one entry per state or position and that every position
+    // named is one of the positions 1 to no_of_states - 1
+    fn check(&self) -> Result<(), String> {
+        let n = self.no_of_states as usize;
+        let position = |x: u8| x >= 1 && (x as usize) < n;
+        if n == 0 {
+            return Err("no_of_states must be at least 1".to_string());
+        }
+        if self.nfa.len() != n {
+            return Err(format!("nfa has {} rows for {} states", self.nfa.len(), n));
+        }
+        if self.state_letter.len() != n - 1 || self.state_labels.len() != n - 1 {
+            return Err(format!("state_letter and state_labels need {} entries", n - 1));
+        }
+        if let Some(class) = self.state_letter.iter().find(|x| x.len() != 256) {
+            return Err(format!("a class has {} entries instead of 256", class.len()));
+        }
+        if let Some(x) = self.nfa.iter().flatten().find(|x| !position(**x)) {
+            return Err(format!("nfa goes to {}, which is not a position", x));
+        }
+        if let Some(x) = self.P_set.iter().chain(&self.D_set).find(|x| !position(**x)) {
+            return Err(format!("P_set or D_set holds {}, which is not a position", x));
+        }
+        if let Some((x, y)) = self.F_set.iter().find(|(x, y)| !position(*x) || !position(*y)) {
+            return Err(format!("F_set holds ({}, {}), which are not positions", x, y));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::compile;
+
+    #[test]
+    fn export_snapshot() {
+        let g = compile("a[bc]*").unwrap();
+        assert_eq!(g.to_json(), concat!(
+            r#"{"ast":{"Concat":[{"Letter":97},{"Star":{"CharClass":[[98,99]]}}]},"#,
+            r#""augmented":{"Concat":[{"Letter":1},{"Star":{"Letter":2}}]},"#,
+            r#""state_labels":["a","[bc]"],"no_of_states":3,"P_set":[1],"D_set":[1,2],"F_set":[[1,2],[2,2]],"#,
+            r#""nfa":[[1],[2],[2]],"state_letter":[[[97,97]],[[98,99]]],"nullable":false}"#
+        ));
+    }
+
+    #[test]
+    fn round_trip() {
+        for pattern in ["a", "(a|b)*c", "[a-z0-9]+x?", "a{2,3}[^b]", "(ab|())c*"] {
+            let g = compile(pattern).unwrap();
+            let h = crate::Glushkov::from_json(&g.to_json()).unwrap();
+            assert_eq!(h.ast, g.ast);
+            assert_eq!(h.augmented, g.augmented);
+            assert_eq!(h.P_set, g.P_set);
+            assert_eq!(h.D_set, g.D_set);
+            assert_eq!(h.F_set, g.F_set);
+            assert_eq!(h.nfa, g.nfa);
+            assert_eq!(h.state_letter, g.state_letter);
+            assert_eq!(h.to_json(), g.to_json());
+        }
+    }
+
+    // Well-typed JSON whose tables do not fit together is rejected instead of panicking later
+    #[test]
+    fn inconsistent_json_is_rejected() {
+        let json = crate::compile("a[bc]*").unwrap().to_json();
+        let corrupted = [
+            json.replace(r#""nfa":[[1],[2],[2]]"#, r#""nfa":[[0],[2],[2]]"#),
+            json.replace(r#""nfa":[[1],[2],[2]]"#, r#""nfa":[[1],[2],[3]]"#),
+            json.replace(r#""nfa":[[1],[2],[2]]"#, r#""nfa":[[1],[2]]"#),
+            json.replace(r#""no_of_states":3"#, r#""no_of_states":4"#),
+            json.replace(r#""no_of_states":3"#, r#""no_of_states":0"#),
+            json.replace(r#""state_letter":[[[97,97]],[[98,99]]]"#, r#""state_letter":[[[97,97]]]"#),
+            json.replace(r#""D_set":[1,2]"#, r#""D_set":[1,7]"#),
+            json.replace(r#""F_set":[[1,2],[2,2]]"#, r#""F_set":[[1,2],[2,0]]"#),
+        ];
+        for s in &corrupted {
+            assert_ne!(s, &json);
+            assert!(crate::Glushkov::from_json(s).is_err(), "{}", s);
+        }
+        assert!(crate::Glushkov::from_json(&json).is_ok());
+    }
+}
@@ -0,0 +1,296 @@
+// Static analysis of the AST: bounds on the length of a match and the literal strings that
+// every match starts with, ends with or contains, used for prefiltering and length guards
+
+use std::collections::BTreeSet;
+use std::ops::Deref;
+use std::rc::Rc;
+
+use crate::Regex;
+use crate::Regex::{Empty, Eps, Letter, CharClass, Or, Concat, Star, And, Not};
+
+// Largest number of strings kept in a set before it is reduced to their common part
+pub const MAX_SET: usize = 16;
+// Largest class expanded into its bytes
+pub const MAX_CLASS: usize = 8;
+
+pub type Strings = BTreeSet<Vec<u8>>;
+
+// What is known about the matches of an expression that matches something: the set of
+// prefixes (suffixes) is such that every match starts (ends) with one of them, the empty
+// string standing for no information, and every match contains all the required strings
+pub struct Info {
+    pub min: usize,
+    // None when matches can be arbitrarily long
+    pub max: Option<usize>,
+    // Every match, when they are few
+    pub exact: Option<Strings>,
+    pub prefixes: Strings,
+    pub suffixes: Strings,
+    pub required: Strings
+}
+
+fn single(s: Vec<u8>) -> Strings {
+    let mut res = Strings::new();
+    res.insert(s);
+    res
+}
+
+// Every string of the first set followed by one of the second
+pub fn cross(x: &Strings, y: &Strings) -> Strings {
+    let mut res = Strings::new();
+    for a in x {
+        for b in y {
+            let mut s = a.clone();
+            s.extend(b);
+            res.insert(s);
+        }
+    }
+    res
+}
+
+fn commonPrefix(x: &Strings) -> Vec<u8> {
+    let first = x.iter().next().unwrap();
+    let len = x.iter().map(|s| s.iter().zip(first).take_while(|(a, b)| a == b).count()).min().unwrap();
+    first[..len].to_vec()
+}
+
+fn commonSuffix(x: &Strings) -> Vec<u8> {
+    let first = x.iter().next().unwrap();
+    let len = x.iter().map(|s| s.iter().rev().zip(first.iter().rev()).take_while(|(a, b)| a == b).count()).min().unwrap();
+    first[first.len()-len..].to_vec()
+}
+
+// Longest string contained in every string of the set
+fn commonSubstring(x: &Strings) -> Vec<u8> {
+    let shortest = x.iter().min_by_key(|s| s.len()).unwrap();
+    for len in (1..=shortest.len()).rev() {
+        for w in shortest.windows(len) {
+            if x.iter().all(|s| s.windows(len).any(|v| v == w)) {
+                return w.to_vec();
+            }
+        }
+    }
+    Vec::new()
+}
+
+// Sets that grew too large are replaced by the part common to all their strings
+fn limit(x: Strings, common: fn(&Strings) -> Vec<u8>) -> Strings {
+    if x.len() > MAX_SET {
+        single(common(&x))
+    } else {
+        x
+    }
+}
+
+fn contains(s: &[u8], w: &[u8]) -> bool {
+    w.is_empty() || s.windows(w.len()).any(|v| v == w)
+}
+
+impl Info {
+    // Info of a finite set of strings
+    fn of_strings(x: Strings) -> Info {
+        Info {
+            min: x.iter().map(|s| s.len()).min().unwrap(),
+            max: x.iter().map(|s| s.len()).max(),
+            prefixes: x.clone(),
+            suffixes: x.clone(),
+            required: Strings::new(),
+            exact: Some(x)
+        }.normalise()
+    }
+
+    fn unknown(min: usize, max: Option<usize>) -> Info {
+        Info {
+            min,
+            max,
+            exact: None,
+            prefixes: single(Vec::new()),
+            suffixes: single(Vec::new()),
+            required: Strings::new()
+        }
+    }
+
+    // Adds to the required strings what the other sets imply, then keeps only the required
+    // strings not contained in another one
+    fn normalise(mut self) -> Info {
+        if let Some(x) = &self.exact {
+            if x.len() > MAX_SET {
+                self.exact = None;
+            }
+        }
+        self.prefixes = limit(self.prefixes, commonPrefix);
+        self.suffixes = limit(self.suffixes, commonSuffix);
+        let mut implied: Vec<Vec<u8>> = vec![commonPrefix(&self.prefixes), commonSuffix(&self.suffixes)];
+        if let Some(x) = &self.exact {
+            implied.push(commonSubstring(x));
+        }
+        self.required.extend(implied);
+        let all: Vec<Vec<u8>> = self.required.iter().cloned().collect();
+        self.required.retain(|s| !s.is_empty() && !all.iter().any(|t| t != s && contains(t, s)));
+        self
+    }
+}
+
+// Analyses an expression, None when it matches nothing
+pub fn analyse(regexp: &Rc<Regex>) -> Option<Info> {
+    match regexp.deref() {
+        Empty() => None,
+        Eps() => Some(Info::of_strings(single(Vec::new()))),
+        Letter(a) => Some(Info::of_strings(single(vec![*a]))),
+        CharClass(charvec) => {
+            let count = charvec.iter().filter(|x| **x).count();
+            if count == 0 {
+                None
+            } else if count <= MAX_CLASS {
+                Some(Info::of_strings((0..256).filter(|x| charvec[*x]).map(|x| vec![x as u8]).collect()))
+            } else {
+                Some(Info::unknown(1, Some(1)))
+            }
+        },
+        Or(r1, r2) => {
+            let (x, y) = match (analyse(r1), analyse(r2)) {
+                (Some(x), Some(y)) => (x, y),
+                (x, y) => return x.or(y)
+            };
+            Some(Info {
+                min: x.min.min(y.min),
+                max: x.max.zip(y.max).map(|(a, b)| a.max(b)),
+                exact: x.exact.zip(y.exact).map(|(a, b)| a.union(&b).cloned().collect()),
+                prefixes: x.prefixes.union(&y.prefixes).cloned().collect(),
+                suffixes: x.suffixes.union(&y.suffixes).cloned().collect(),
+                required: x.required.intersection(&y.required).cloned().collect()
+            }.normalise())
+        },
+        Concat(r1, r2) => {
+            let x = analyse(r1)?;
+            let y = analyse(r2)?;
+            // A prefix of the first operand matching it whole goes on with the prefixes of the second
+            let prefixes = match &x.exact {
+                Some(a) => cross(a, &y.prefixes),
+                None => x.prefixes
+            };
+            let suffixes = match &y.exact {
+                Some(b) => cross(&x.suffixes, b),
+                None => y.suffixes
+            };
+            let mut required = x.required;
+            required.extend(y.required);
+            Some(Info {
+                min: x.min + y.min,
+                max: x.max.zip(y.max).map(|(a, b)| a + b),
+                exact: x.exact.zip(y.exact).map(|(a, b)| cross(&a, &b)),
+                prefixes,
+                suffixes,
+                required
+            }.normalise())
+        },
+        Star(r1) => match analyse(r1) {
+            Some(x) if x.max != Some(0) => Some(Info::unknown(0, None)),
+            _ => Some(Info::of_strings(single(Vec::new())))
+        },
+        And(r1, r2) => {
+            let x = analyse(r1)?;
+            let y = analyse(r2)?;
+            match (&x.exact, &y.exact) {
+                (Some(a), Some(b)) => {
+                    let both: Strings = a.intersection(b).cloned().collect();
+                    return if both.is_empty() { None } else { Some(Info::of_strings(both)) };
+                },
+                // The matches are among the strings of one operand, without being all of them
+                (Some(a), None) | (None, Some(a)) => {
+                    let mut res = Info::of_strings(a.clone());
+                    res.exact = None;
+                    res.required.extend(x.required);
+                    res.required.extend(y.required);
+                    return Some(res.normalise());
+                },
+                (None, None) => {}
+            }
+            let mut required = x.required;
+            required.extend(y.required);
+            // Both sets of prefixes hold, the one with the longest strings says the most
+            let shortest = |s: &Strings| s.iter().map(|w| w.len()).min().unwrap();
+            let prefixes = if shortest(&x.prefixes) >= shortest(&y.prefixes) { x.prefixes } else { y.prefixes };
+            let suffixes = if shortest(&x.suffixes) >= shortest(&y.suffixes) { x.suffixes } else { y.suffixes };
+            let max = match (x.max, y.max) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b)
+            };
+            Some(Info {
+                min: x.min.max(y.min),
+                max,
+                exact: None,
+                prefixes,
+                suffixes,
+                required
+            }.normalise())
+        },
+        Not(_) => Some(Info::unknown(0, None))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dfa;
+    use crate::parse;
+    use crate::tests::all_strings;
+
+    fn info(pattern: &str) -> Option<Info> {
+        analyse(&parse(pattern).unwrap().0)
+    }
+
+    fn strings(x: &[&str]) -> Strings {
+        x.iter().map(|s| s.as_bytes().to_vec()).collect()
+    }
+
+    #[test]
+    fn known_patterns() {
+        let x = info("ab(c|d)").unwrap();
+        assert_eq!((x.min, x.max), (3, Some(3)));
+        assert_eq!(x.exact, Some(strings(&["abc", "abd"])));
+        assert_eq!(x.required, strings(&["ab"]));
+        let x = info("foo.*bar").unwrap();
+        assert_eq!((x.min, x.max), (6, None));
+        assert_eq!(x.prefixes, strings(&["foo"]));
+        assert_eq!(x.suffixes, strings(&["bar"]));
+        assert_eq!(x.required, strings(&["bar", "foo"]));
+        assert!(info("a&b").is_none());
+        assert!(info("[^\\x00-\\xff]").is_none());
+    }
+
+    // Every string the pattern matches must agree with what the analysis says about its matches
+    #[test]
+    fn sound_against_brute_force() {
+        let patterns = [
+            "abc", "a(b|c)*a", "(ab|ac)c*", "a*b", "(a|b)(a|c)", "[abc]{2}b", "(abc)*", "a+&.*b", "~(ab)",
+            "ab&a.", "(a|b)*abb", "c?(ba|bb)c", "(a|())b(c|())", "[ab]+&(.*c.*)",
+        ];
+        let all = all_strings(b"abc", 6);
+        for pattern in patterns {
+            let d = dfa::compile(pattern).unwrap();
+            let matches: Vec<&Vec<u8>> = all.iter().filter(|s| d.accepts(s)).collect();
+            let x = match info(pattern) {
+                Some(x) => x,
+                None => {
+                    assert!(matches.is_empty(), "{}", pattern);
+                    continue;
+                }
+            };
+            if let Some(exact) = &x.exact {
+                for s in exact {
+                    assert!(d.accepts(s), "{} {:?}", pattern, s);
+                }
+            }
+            for s in matches {
+                assert!(x.min <= s.len() && x.max.is_none_or(|m| s.len() <= m), "{} {:?}", pattern, s);
+                if let Some(exact) = &x.exact {
+                    assert!(exact.contains(s), "{} {:?}", pattern, s);
+                }
+                assert!(x.prefixes.iter().any(|p| s.starts_with(p)), "{} {:?}", pattern, s);
+                assert!(x.suffixes.iter().any(|p| s.ends_with(p)), "{} {:?}", pattern, s);
+                assert!(x.required.iter().all(|w| contains(s, w)), "{} {:?}", pattern, s);
+            }
+        }
+    }
+}
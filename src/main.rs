@@ -9,26 +9,120 @@ use std::io;
 extern crate pest;
 #[macro_use]
 extern crate pest_derive;
+extern crate rand;
+extern crate serde;
+extern crate serde_json;
 
 use pest::Parser;
+use serde::{Deserialize, Serialize};
 
 #[derive(Parser)]
 #[grammar = "grammar.pest"]
 struct RegEx;
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum Regex {
     Empty(),
     Eps(),
     Letter(u8),
-    CharClass(Vec<bool>),
+    CharClass(#[serde(with = "json::class_ranges")] Vec<bool>),
     Or(Rc<Regex>, Rc<Regex>),
     Concat(Rc<Regex>, Rc<Regex>),
-    Star(Rc<Regex>)
+    Star(Rc<Regex>),
+    And(Rc<Regex>, Rc<Regex>),
+    Not(Rc<Regex>)
 }
 
 
-use crate::Regex::{Empty, Eps, Letter, CharClass, Or, Concat, Star};
+use crate::Regex::{Empty, Eps, Letter, CharClass, Or, Concat, Star, And, Not};
+
+mod ahocorasick;
+mod ambiguity;
+mod automaton;
+mod classes;
+mod count;
+mod derivative;
+mod determinism;
+mod dfa;
+mod dot;
+mod eliminate;
+mod emptiness;
+mod engine;
+mod enumerate;
+mod equiv;
+mod explain;
+mod json;
+mod literals;
+mod prefilter;
+mod pretty;
+mod redos;
+mod reverse;
+mod sample;
+mod simplify;
+mod symbolic;
+mod trigram;
+
+// Glushkov automaton of a regular expression together with the sets it is built from
+#[derive(Serialize, Deserialize)]
+pub struct Glushkov {
+    pub ast: Rc<Regex>,
+    pub augmented: Rc<Regex>,
+    pub state_labels: Vec<String>,
+    pub no_of_states: u8,
+    #[serde(serialize_with = "json::sorted_set")]
+    pub P_set: HashSet<u8>,
+    #[serde(serialize_with = "json::sorted_set")]
+    pub D_set: HashSet<u8>,
+    #[serde(serialize_with = "json::sorted_set")]
+    pub F_set: HashSet<(u8, u8)>,
+    pub nfa: Vec<Vec<u8>>,
+    #[serde(with = "json::class_list")]
+    pub state_letter: Vec<Vec<bool>>,
+    pub nullable: bool
+}
+
+impl Glushkov {
+    // Checks whether a state is accepting, state 0 being accepting only for nullable expressions
+    pub fn is_final(&self, state: u8) -> bool {
+        if state == 0 {
+            self.nullable
+        } else {
+            checkstate(state as usize, &self.D_set)
+        }
+    }
+
+    // Finds the states reachable from the given states on reading a byte
+    pub fn step(&self, states: &[u8], byte: u8) -> Vec<u8> {
+        let mut next: Vec<u8> = Vec::new();
+        for x in states {
+            for i in &self.nfa[*x as usize] {
+                if self.state_letter[(i-1) as usize][byte as usize] && !next.contains(i) {
+                    next.push(*i);
+                }
+            }
+        }
+        next.sort();
+        next
+    }
+
+    // Checks whether the whole string is matched
+    pub fn accepts(&self, s: &[u8]) -> bool {
+        let mut curr: Vec<u8> = vec![0];
+        for byte in s {
+            curr = self.step(&curr, *byte);
+        }
+        curr.iter().any(|x| self.is_final(*x))
+    }
+
+    // Label of a state, state 0 being the initial state
+    pub fn label(&self, state: u8) -> String {
+        if state == 0 {
+            "start".to_string()
+        } else {
+            self.state_labels[(state-1) as usize].clone()
+        }
+    }
+}
 
 // Finds whether a regular expression is nullable
 fn findLambda (regexp: &Rc<Regex>) -> Rc<Regex> {
@@ -55,7 +149,23 @@ fn findLambda (regexp: &Rc<Regex>) -> Rc<Regex> {
                 Rc::new(Empty())
             }
         },
-        Star(_)    => Rc::new(Eps())
+        Star(_)    => Rc::new(Eps()),
+        And(r1, r2) => {
+            let s1 = findLambda(r1);
+            let s2 = findLambda(r2);
+            if matches!(*s1.deref(), Eps()) && matches!(*s2.deref(), Eps()) {
+                Rc::new(Eps())
+            } else {
+                Rc::new(Empty())
+            }
+        },
+        Not(r1) => {
+            if matches!(*findLambda(r1).deref(), Eps()) {
+                Rc::new(Empty())
+            } else {
+                Rc::new(Eps())
+            }
+        }
     }
 }
 
@@ -68,7 +178,7 @@ fn constructP (regexp: &Rc<Regex>) -> HashSet<u8> {
             let union_s: HashSet<_> = s1.union(&s2).collect();
             let mut hset: HashSet<u8> = HashSet::new();
             for x in union_s {
-                hset.insert(x.clone());
+                hset.insert(*x);
                 // println!("Hello11 : {}", *x);
             }
             // println!("End11");
@@ -88,7 +198,7 @@ fn constructP (regexp: &Rc<Regex>) -> HashSet<u8> {
                     let union_s: HashSet<_> = s1.union(&s2).collect();
                     let mut hset: HashSet<u8> = HashSet::new();
                     for x in union_s {
-                        hset.insert(x.clone());
+                        hset.insert(*x);
                         // println!("Hello : {}", *x);
                     }
                     // println!("End");
@@ -123,7 +233,7 @@ fn constructD (regexp: &Rc<Regex>) -> HashSet<u8> {
             let union_s: HashSet<_> = s1.union(&s2).collect();
             let mut hset: HashSet<u8> = HashSet::new();
             for x in union_s {
-                hset.insert(x.clone());
+                hset.insert(*x);
                 // println!("Hello11 : {}", *x);
             }
             // println!("End11");
@@ -143,7 +253,7 @@ fn constructD (regexp: &Rc<Regex>) -> HashSet<u8> {
                     let union_s: HashSet<_> = s1.union(&s2).collect();
                     let mut hset: HashSet<u8> = HashSet::new();
                     for x in union_s {
-                        hset.insert(x.clone());
+                        hset.insert(*x);
                         // println!("Hello : {}", *x);
                     }
                     // println!("End");
@@ -158,7 +268,7 @@ fn constructD (regexp: &Rc<Regex>) -> HashSet<u8> {
         Star(r1) => constructD(r1),
         Letter(x) => {
             let mut hset: HashSet<u8> = HashSet::new();
-            hset.insert(x.clone());
+            hset.insert(*x);
             hset
         },
         _ => {
@@ -186,7 +296,7 @@ fn constructF (regexp: &Rc<Regex>) -> HashSet<(u8, u8)> {
             let union_s: HashSet<_> = s1.union(&s2).collect();
             let mut hset: HashSet<(u8, u8)> = HashSet::new();
             for x in union_s {
-                hset.insert(x.clone());
+                hset.insert(*x);
                 // println!("Finial union or: {:?}", *x);
             }
             // println!("End10");
@@ -206,7 +316,7 @@ fn constructF (regexp: &Rc<Regex>) -> HashSet<(u8, u8)> {
             let mut hset0: HashSet<(u8, u8)> = HashSet::new();
             let union_helper: HashSet<_> = s1.union(&s2).collect();
             for x in union_helper {
-                hset0.insert(x.clone());
+                hset0.insert(*x);
                 // println!("in union helper concat : {:?}", *x);
             }
             // println!("End union helper concat");
@@ -215,7 +325,7 @@ fn constructF (regexp: &Rc<Regex>) -> HashSet<(u8, u8)> {
             let mut hset1: HashSet<(u8, u8)> = HashSet::new();
             for x in hs1 {
                 for y in &hs2 {
-                    hset1.insert((x.clone(), y.clone()));
+                    hset1.insert((x, *y));
                 }
             }
             // for x in &hset1 {
@@ -225,7 +335,7 @@ fn constructF (regexp: &Rc<Regex>) -> HashSet<(u8, u8)> {
             let union_s: HashSet<_> = hset0.union(&hset1).collect();
             let mut hset: HashSet<(u8, u8)> = HashSet::new();
             for x in union_s {
-                hset.insert(x.clone());
+                hset.insert(*x);
                 // println!("Hello12 : {:?}", *x);
             }
             // for x in &hset {
@@ -245,13 +355,13 @@ fn constructF (regexp: &Rc<Regex>) -> HashSet<(u8, u8)> {
             let mut hset0: HashSet<(u8, u8)> = HashSet::new();
             for x in hs1 {
                 for y in &hs2 {
-                    hset0.insert((x.clone(), y.clone()));
+                    hset0.insert((x, *y));
                 }
             }
             let union_s: HashSet<_> = s1.union(&hset0).collect();
             let mut hset: HashSet<(u8, u8)> = HashSet::new();
             for x in union_s {
-                hset.insert(x.clone());
+                hset.insert(*x);
                 // println!("Hello12 : {:?}", *x);
             }
             hset
@@ -287,11 +397,11 @@ fn checkstr(s: &str, nfa: &Vec<Vec<u8>>, final_states: &HashSet<u8>, curr: usize
     } else if !pass && idx == s.len() {
         res = false;
     } else {
-        for i in &nfa[curr as usize] {
+        for i in &nfa[curr] {
             // println!("Curr state = {} Checking state {} idx = {}, judgement of final ={}, final states : {:?}", curr,  *i, idx, checkstate(curr, final_states), final_states);
             if state_letter[(i-1) as usize][s.chars().nth(idx).unwrap() as usize] {
                 println!("Char encountered: {}, curr state: {}, Going to State: {}", s.chars().nth(idx).unwrap(), curr, *i);
-                res = res || checkstr(s, &nfa, &final_states, *i as usize, idx+1, &state_letter);
+                res = res || checkstr(s, nfa, final_states, *i as usize, idx+1, state_letter);
             }
             if res {
                 break;
@@ -305,28 +415,28 @@ fn checkstr(s: &str, nfa: &Vec<Vec<u8>>, final_states: &HashSet<u8>, curr: usize
 fn augment(regexp: &Rc<Regex>, cnt: &mut u8) -> Rc<Regex> {
     match regexp.deref() {
         Letter(_) => {
-            *cnt = *cnt + 1;
+            *cnt += 1;
             // println!("cnt {}", a);
             // println!("cnt {}", ((*cnt - 1)*10 + a));
             let x1 = *cnt - 1;
-            let val = x1 as u8;
+            let val = x1;
             // let ret = Rc::new(Letter(format!("{}{}", a, val)));
-            let ret = Rc::new(Letter(val));
-            ret
+            Rc::new(Letter(val))
         },
         CharClass(_) => {
-            *cnt = *cnt + 1;
+            *cnt += 1;
             // println!("cnt {}", a);
             // println!("cnt {}", ((*cnt - 1)*10 + a));
             let x1 = *cnt - 1;
-            let val = x1 as u8;
+            let val = x1;
             // let ret = Rc::new(Letter(format!("{}{}", a, val)));
-            let ret = Rc::new(Letter(val));
-            ret
+            Rc::new(Letter(val))
         }
         Or(r1, r2) => Rc::new(Or(augment(r1, cnt), augment(r2, cnt))),
         Concat(r1, r2) => Rc::new(Concat(augment(r1, cnt), augment(r2, cnt))),
         Star(r1) => Rc::new(Star(augment(r1, cnt))),
+        And(r1, r2) => Rc::new(And(augment(r1, cnt), augment(r2, cnt))),
+        Not(r1) => Rc::new(Not(augment(r1, cnt))),
         Empty() => Rc::new(Empty()),
         Eps() => Rc::new(Eps())
     }
@@ -358,7 +468,7 @@ fn augment(regexp: &Rc<Regex>, cnt: &mut u8) -> Rc<Regex> {
 fn addstates(regexp: &Rc<Regex>, state_letter: &mut Vec<Vec<bool>>) {
     match regexp.deref() {
         Letter(a) => {
-            let t0 = a.clone() as u8;
+            let t0 = *a;
             let mut v1: Vec<bool> = vec![false; 256];
             v1[t0 as usize] = true;
             state_letter.push(v1.clone());
@@ -379,6 +489,20 @@ fn addstates(regexp: &Rc<Regex>, state_letter: &mut Vec<Vec<bool>>) {
     }
 }
 
+// Generates the labels of the letter labelled states from the AST, in the same order as addstates
+fn addlabels(regexp: &Rc<Regex>, state_labels: &mut Vec<String>) {
+    match regexp.deref() {
+        Letter(a) => state_labels.push(letterLabel(*a)),
+        CharClass(a) => state_labels.push(classLabel(a)),
+        Or(r1, r2) | Concat(r1, r2) => {
+            addlabels(r1, state_labels);
+            addlabels(r2, state_labels)
+        },
+        Star(r1) => addlabels(r1, state_labels),
+        _ => {}
+    }
+}
+
 // Generates Number
 fn getNUM(token: &pest::iterators::Pair<Rule>, val: &mut u32) -> u32 {
     let mut tmp0 = token.clone().into_inner();
@@ -387,7 +511,8 @@ fn getNUM(token: &pest::iterators::Pair<Rule>, val: &mut u32) -> u32 {
             getNUM(&tmp0.next().unwrap(), val)
         },
         Rule::Number    =>  {
-            *val = *val*10 + token.as_str().parse::<u32>().unwrap();
+            // Leading digit first, then the rest of the number
+            getNUM(&tmp0.next().unwrap(), val);
             getNUM(&tmp0.next().unwrap(), val)
         },
         Rule::Integer   =>  {
@@ -406,18 +531,18 @@ fn getNUM(token: &pest::iterators::Pair<Rule>, val: &mut u32) -> u32 {
 
 fn getQuantified(regexp: &Rc<Regex>, a: &mut u32, b: &mut u32) -> Rc<Regex>{
     if *a>0 {
-        *a = *a - 1;
-        *b = *b - 1;
+        *a -= 1;
+        *b -= 1;
         let tmpregex = regexp.clone();
         Rc::new(Concat(tmpregex, getQuantified(regexp, a, b)))
     }
     else if *a == 0 && *b > 1 {
-        *b = *b - 1;
+        *b -= 1;
         let tmpregex = regexp.clone();
         Rc::new(Concat(Rc::new(Or(tmpregex, Rc::new(Eps()))), getQuantified(regexp, a, b)))
     }
     else if *a == 0 && *b == 1 {
-        *b = *b - 1;
+        *b -= 1;
         let tmpregex = regexp.clone();
         Rc::new(Or(tmpregex, Rc::new(Eps())))
     }
@@ -430,7 +555,7 @@ fn getQuantified(regexp: &Rc<Regex>, a: &mut u32, b: &mut u32) -> Rc<Regex>{
 
 fn getLQuantified(regexp: &Rc<Regex>, a: &mut u32) -> Rc<Regex>{
     if *a>0 {
-        *a = *a - 1;
+        *a -= 1;
         let tmpregex = regexp.clone();
         Rc::new(Concat(tmpregex, getLQuantified(regexp, a)))
     }
@@ -467,14 +592,14 @@ fn getCharClass(token: &pest::iterators::Pair<Rule>, charvec: &mut Vec<bool>) {
             // let t2: pest::iterators::Pair<Rule> = tmp1.next().unwrap(); 
             // let b = t2.as_str().chars().nth(0).unwrap() as u8;
             let b = getletter(&tmp0.next().unwrap());
-            for i in a..(b+1) {
+            for i in a..=b {
                 charvec[i as usize] ^= true; 
             }
             // getCharClass(&tmp0.next().unwrap(), &mut charvec);
         },
         Rule::Letter => {
             // let tmp1 = tmp0.next().unwrap().into_inner();
-            let a = token.as_str().chars().nth(0).unwrap() as u8;
+            let a = letterByte(token);
             // println!("{}", a as char);
             // let a = tmp1.next().unwrap().as_str().chars().nth(0).unwrap() as u8;
             // let b = tmp1.next().unwrap().as_str().chars().nth(0).unwrap() as u8;
@@ -485,67 +610,45 @@ fn getCharClass(token: &pest::iterators::Pair<Rule>, charvec: &mut Vec<bool>) {
     }
 }
 
+// Byte written by a Letter token, either the character itself or a \xNN escape
+fn letterByte(token: &pest::iterators::Pair<Rule>) -> u8 {
+    let s = token.as_str();
+    if let Some(hex) = s.strip_prefix("\\x") {
+        u8::from_str_radix(hex, 16).unwrap()
+    } else {
+        s.as_bytes()[0]
+    }
+}
+
 fn getletter(token: &pest::iterators::Pair<Rule>) -> u8 {
     // let mut tmp0 = token.clone().into_inner();
     match token.as_rule() {
         Rule::Letter => {
-            let val = token.as_str().chars().nth(0).unwrap() as u8;
+            let val = letterByte(token);
             // println!("{}", val as char);
             val
         },
         _ => {
             println!("dont know");
-            1 as u8
+            1_u8
         }
     }
 }
 
-// Parses a given pair to AST
-fn parse_to_AST(token: &pest::iterators::Pair<Rule>) -> Rc<Regex> {
+// Nests a sequence of operands to the right, a|b|c being read as a|(b|c)
+fn foldRight(operands: Vec<Rc<Regex>>, op: fn(Rc<Regex>, Rc<Regex>) -> Regex) -> Rc<Regex> {
+    operands.into_iter().rev().reduce(|r2, r1| Rc::new(op(r1, r2))).unwrap()
+}
+
+// Applies a postfix operator to the expression it follows
+fn getPostfixed(regexp: &Rc<Regex>, token: &pest::iterators::Pair<Rule>) -> Rc<Regex> {
     let mut tmp0 = token.clone().into_inner();
-    // println!("Yes : {:#?}", token);
     match token.as_rule() {
-        Rule::Regex   => {
-            // println!("in Exp");
-            parse_to_AST(&tmp0.next().unwrap())
-        },
-        Rule::Or=> {
-            // println!("Concat - ");
-            let r1 = parse_to_AST(&tmp0.next().unwrap());
-            let r2 = parse_to_AST(&tmp0.next().unwrap());
-            Rc::new(Or(r1, r2))
-        },
-        Rule::T0    => {
-            // println!("in T0 ");
-            parse_to_AST(&tmp0.next().unwrap())
-        },
-        Rule::Concat=> {
-            // println!("Concat - ");
-            let r1 = parse_to_AST(&tmp0.next().unwrap());
-            let r2 = parse_to_AST(&tmp0.next().unwrap());
-            Rc::new(Concat(r1, r2))
-        },
-        Rule::T1    => {
-            // println!("in T1 ");
-            parse_to_AST(&tmp0.next().unwrap())
-        },
-        Rule::Star  => {
-            // println!("Star - ");
-            Rc::new(Star(parse_to_AST(&tmp0.next().unwrap())))
-        },
-        Rule::Plus  => {
-            // println!("Plus - ");
-            let tmp1 = parse_to_AST(&tmp0.next().unwrap());
-            let tmp2 = tmp1.clone();
-            Rc::new(Concat(tmp1, Rc::new(Star(tmp2))))
-        },
-        Rule::QMark  => {
-            // println!("Plus - ");
-            let tmp1 = parse_to_AST(&tmp0.next().unwrap());
-            Rc::new(Or(tmp1, Rc::new(Eps())))
-        },
+        Rule::Postfix => getPostfixed(regexp, &tmp0.next().unwrap()),
+        Rule::Star  => Rc::new(Star(regexp.clone())),
+        Rule::Plus  => Rc::new(Concat(regexp.clone(), Rc::new(Star(regexp.clone())))),
+        Rule::QMark => Rc::new(Or(regexp.clone(), Rc::new(Eps()))),
         Rule::Quantifier    =>  {
-            let tmp1 = parse_to_AST(&tmp0.next().unwrap());
             let mut tnum1: u32 = 0;
             let mut num1 = getNUM(&tmp0.next().unwrap(), &mut tnum1);
             let mut tnum2: u32 = 0;
@@ -553,29 +656,64 @@ fn parse_to_AST(token: &pest::iterators::Pair<Rule>) -> Rc<Regex> {
             if num1>num2 {
                 println!("In Quantifiers, left boundary cannot be greater than right boundary");
             }
-            getQuantified(&tmp1, &mut num1, &mut num2)
+            getQuantified(regexp, &mut num1, &mut num2)
         },
         Rule::LQuantifier    =>  {
-            let tmp1 = parse_to_AST(&tmp0.next().unwrap());
             let mut tnum1: u32 = 0;
             let mut num1 = getNUM(&tmp0.next().unwrap(), &mut tnum1);
-            // let mut tnum2: u32 = 0;
-            // let mut num2 = getNUM(&tmp0.next().unwrap(), &mut tnum2);
-            getLQuantified(&tmp1, &mut num1)
+            getLQuantified(regexp, &mut num1)
         },
         Rule::UQuantifier    =>  {
-            let tmp1 = parse_to_AST(&tmp0.next().unwrap());
             let mut tnum1: u32 = 0;
             let mut tnum2: u32 = 0;
             let mut num2 = getNUM(&tmp0.next().unwrap(), &mut tnum2);
-            getQuantified(&tmp1, &mut tnum1, &mut num2)
+            getQuantified(regexp, &mut tnum1, &mut num2)
         },
         Rule::FQuantifier    =>  {
-            let tmp1 = parse_to_AST(&tmp0.next().unwrap());
             let mut tnum2: u32 = 0;
             let mut num2 = getNUM(&tmp0.next().unwrap(), &mut tnum2);
             let mut tnum1: u32 = num2;
-            getQuantified(&tmp1, &mut tnum1, &mut num2)
+            getQuantified(regexp, &mut tnum1, &mut num2)
+        },
+        _ => {
+            println!("Strange encountered in Postfix");
+            regexp.clone()
+        }
+    }
+}
+
+// Parses a given pair to AST
+fn parse_to_AST(token: &pest::iterators::Pair<Rule>) -> Rc<Regex> {
+    let mut tmp0 = token.clone().into_inner();
+    // println!("Yes : {:#?}", token);
+    match token.as_rule() {
+        Rule::Regex   => {
+            // Alternatives
+            foldRight(tmp0.map(|x| parse_to_AST(&x)).collect(), Or)
+        },
+        Rule::A0    => {
+            // Intersections
+            foldRight(tmp0.map(|x| parse_to_AST(&x)).collect(), And)
+        },
+        Rule::T0    => {
+            // Concatenations
+            foldRight(tmp0.map(|x| parse_to_AST(&x)).collect(), Concat)
+        },
+        Rule::T1    => {
+            // Leading ~ apply after the postfix operator, ~a* being ~(a*)
+            let mut nots = 0;
+            let mut res = Rc::new(Eps());
+            for x in tmp0 {
+                match x.as_rule() {
+                    Rule::Not => nots += 1,
+                    Rule::Postfix => res = getPostfixed(&res, &x),
+                    _ => res = parse_to_AST(&x)
+                }
+            }
+            for _ in 0..nots {
+                res = Rc::new(Not(res));
+            }
+            res
         },
         Rule::T2    => {
             // println!("in T2 ");
@@ -587,11 +725,6 @@ fn parse_to_AST(token: &pest::iterators::Pair<Rule>) -> Rc<Regex> {
             parse_to_AST(&tmp0.next().unwrap())
         },
 
-        Rule::T3    => {
-            // println!("in T3 ");
-            parse_to_AST(&tmp0.next().unwrap())
-        },
-
         Rule::T4    => {
             // println!("in T4 ");
             parse_to_AST(&tmp0.next().unwrap())
@@ -615,7 +748,9 @@ fn parse_to_AST(token: &pest::iterators::Pair<Rule>) -> Rc<Regex> {
         //     // println!("in T2 ");
         //     parse_to_AST(&tmp0.next().unwrap())
         // },
-        Rule::Letter => Rc::new(Letter(token.as_str().chars().nth(0).unwrap() as u8)),
+        Rule::EmptyParen => Rc::new(Eps()),
+        Rule::Dot => Rc::new(CharClass(vec![true; 256])),
+        Rule::Letter => Rc::new(Letter(letterByte(token))),
         _ => {
             println!("Empty Generated");
             Rc::new(Eps())
@@ -623,55 +758,47 @@ fn parse_to_AST(token: &pest::iterators::Pair<Rule>) -> Rc<Regex> {
     }
 }
 
-fn getStateLabels(token: &pest::iterators::Pair<Rule>, state_labels: &mut Vec<String>) {
+// Number of copies getPostfixed makes of the expression a postfix operator follows
+fn getCopies(token: &pest::iterators::Pair<Rule>) -> u32 {
     let mut tmp0 = token.clone().into_inner();
-    // println!("Yes : {:#?}", token);
     match token.as_rule() {
-        Rule::Regex   => {
-            // println!("in Exp");
-            getStateLabels(&tmp0.next().unwrap(), state_labels);
-        },
-        Rule::Or=> {
-            // println!("Concat - ");
-            getStateLabels(&tmp0.next().unwrap(), state_labels);
-            getStateLabels(&tmp0.next().unwrap(), state_labels);
-        },
-        Rule::T0    => {
-            // println!("in T0 ");
-            getStateLabels(&tmp0.next().unwrap(), state_labels);
-        },
-        Rule::Concat=> {
-            // println!("Concat - ");
-            getStateLabels(&tmp0.next().unwrap(), state_labels);
-            getStateLabels(&tmp0.next().unwrap(), state_labels);
-        },
-        Rule::T1    => {
-            // println!("in T1 ");
-            getStateLabels(&tmp0.next().unwrap(), state_labels);
-        },
-        Rule::Star  => {
-            // println!("Star - ");
-            getStateLabels(&tmp0.next().unwrap(), state_labels);
-        },
-        Rule::Plus  => {
-            // println!("Plus - ");
-            getStateLabels(&tmp0.next().unwrap(), state_labels);
-        },
-        Rule::QMark  => {
-            // println!("Plus - ");
-            getStateLabels(&tmp0.next().unwrap(), state_labels);
-        },
+        Rule::Postfix => getCopies(&tmp0.next().unwrap()),
+        Rule::Plus => 2,
         Rule::Quantifier    =>  {
-            getStateLabels(&tmp0.next().unwrap(), state_labels);
+            let mut tnum1: u32 = 0;
+            let num1 = getNUM(&tmp0.next().unwrap(), &mut tnum1);
+            let mut tnum2: u32 = 0;
+            let num2 = getNUM(&tmp0.next().unwrap(), &mut tnum2);
+            num1.max(num2)
         },
         Rule::LQuantifier    =>  {
-            getStateLabels(&tmp0.next().unwrap(), state_labels);
+            let mut tnum1: u32 = 0;
+            getNUM(&tmp0.next().unwrap(), &mut tnum1) + 1
         },
-        Rule::UQuantifier    =>  {
-            getStateLabels(&tmp0.next().unwrap(), state_labels);
+        Rule::UQuantifier | Rule::FQuantifier    =>  {
+            let mut tnum2: u32 = 0;
+            getNUM(&tmp0.next().unwrap(), &mut tnum2)
         },
-        Rule::FQuantifier    =>  {
-            getStateLabels(&tmp0.next().unwrap(), state_labels);
+        _ => 1
+    }
+}
+
+fn getStateLabels(token: &pest::iterators::Pair<Rule>, state_labels: &mut Vec<String>) {
+    let mut tmp0 = token.clone().into_inner();
+    // println!("Yes : {:#?}", token);
+    match token.as_rule() {
+        Rule::Regex | Rule::A0 | Rule::T0   => {
+            for x in tmp0 {
+                getStateLabels(&x, state_labels);
+            }
+        },
+        Rule::T1    => {
+            let operand = tmp0.find(|x| x.as_rule() == Rule::T2).unwrap();
+            let copies = match tmp0.next() {
+                Some(postfix) => getCopies(&postfix),
+                None => 1
+            };
+            getRepeatedLabels(&operand, copies, state_labels);
         },
         Rule::T2    => {
             // println!("in T2 ");
@@ -683,11 +810,6 @@ fn getStateLabels(token: &pest::iterators::Pair<Rule>, state_labels: &mut Vec<St
             getStateLabels(&tmp0.next().unwrap(), state_labels);
         },
 
-        Rule::T3    => {
-            // println!("in T3 ");
-            getStateLabels(&tmp0.next().unwrap(), state_labels);
-        },
-
         Rule::T4    => {
             // println!("in T4 ");
             getStateLabels(&tmp0.next().unwrap(), state_labels);
@@ -709,6 +831,10 @@ fn getStateLabels(token: &pest::iterators::Pair<Rule>, state_labels: &mut Vec<St
         //     // println!("in T2 ");
         //     parse_to_AST(&tmp0.next().unwrap())
         // },
+        Rule::EmptyParen => {},
+        Rule::Dot => {
+            state_labels.push(".".to_string());
+        },
         Rule::Letter => {
             state_labels.push(token.as_str().to_string());
         },
@@ -718,6 +844,16 @@ fn getStateLabels(token: &pest::iterators::Pair<Rule>, state_labels: &mut Vec<St
     }
 }
 
+// Adds the labels of a repeated subexpression once for every copy getPostfixed makes of it,
+// so that the labels line up with the positions numbered by augment
+fn getRepeatedLabels(token: &pest::iterators::Pair<Rule>, copies: u32, state_labels: &mut Vec<String>) {
+    let mut tmp_labels: Vec<String> = Vec::new();
+    getStateLabels(token, &mut tmp_labels);
+    for _ in 0..copies {
+        state_labels.extend(tmp_labels.iter().cloned());
+    }
+}
+
 fn get_classlabel(token: &pest::iterators::Pair<Rule>) -> String {
     let mut tmp0 = token.clone().into_inner();
     match token.as_rule() {
@@ -751,66 +887,145 @@ fn get_classlabel(token: &pest::iterators::Pair<Rule>) -> String {
             s1
         },
         _   =>    {
-            let s1 = "".to_string();
-            s1
+            "".to_string()
         }
     }
 }
 
 
-fn main() {
-
-    println!("Enter a RegEx:");
-    let mut tmp1 = String::new();
-    io::stdin().read_line(&mut tmp1).expect("failed to readline");
-    let regex_input = tmp1.trim();
-    // let regex_input = "[abc-z0-1]{,1}";
-    // let regex_input = "bbc[^5-9]*[abc-z0-1]{3, 4}";
-    // let regex_input = "bbc[^5-9]*[abc-z0-1]{3, 4}3?dd";
-    // let regex_input = "[abc-z0-1]{,1}";
+// Writes a byte the way it appears inside a pattern, escaping anything that is not a letter or digit
+fn letterLabel(a: u8) -> String {
+    if a.is_ascii_alphanumeric() {
+        (a as char).to_string()
+    } else {
+        format!("\\x{:02x}", a)
+    }
+}
 
+// Lists the ranges of consecutive bytes present in a character class
+fn classRanges(charvec: &[bool]) -> Vec<(u8, u8)> {
+    let mut ranges: Vec<(u8, u8)> = Vec::new();
+    let mut i = 0;
+    while i < 256 {
+        if charvec[i] {
+            let start = i;
+            while i + 1 < 256 && charvec[i + 1] {
+                i += 1;
+            }
+            ranges.push((start as u8, i as u8));
+        }
+        i += 1;
+    }
+    ranges
+}
 
+// Renders a character class compactly, as a single letter, a list of ranges or a negated list of ranges
+fn classLabel(charvec: &[bool]) -> String {
+    let ranges = classRanges(charvec);
+    if ranges.len() == 1 && ranges[0].0 == ranges[0].1 {
+        return letterLabel(ranges[0].0);
+    }
+    if ranges.len() == 1 && ranges[0] == (0, 255) {
+        return ".".to_string();
+    }
+    let negated: Vec<bool> = charvec.iter().map(|x| !x).collect();
+    let neg_ranges = classRanges(&negated);
+    let (prefix, shown) = if ranges.is_empty() {
+        // Nothing in the class, written as the negation of every byte
+        ("^", vec![(0, 255)])
+    } else if !neg_ranges.is_empty() && neg_ranges.len() < ranges.len() {
+        ("^", neg_ranges)
+    } else {
+        ("", ranges)
+    };
+    let mut res = format!("[{}", prefix);
+    for (a, b) in shown {
+        if a == b {
+            res.push_str(&letterLabel(a));
+        } else {
+            res.push_str(&format!("{}-{}", letterLabel(a), letterLabel(b)));
+        }
+    }
+    res.push(']');
+    res
+}
 
-    // println!("Enter a string:");
-    // println!("Enter a string:");
-    println!("Enter a string:");
-    let mut tmp2 = String::new();
-    io::stdin().read_line(&mut tmp2).expect("failed to readline");
-    let s: &str= tmp2.trim();
-    // let s: &str = "bbc333mn03dd";
-    
+// Parses a regular expression to its AST along with the labels of its positions as written in the pattern
+fn parse(regex_input: &str) -> Result<(Rc<Regex>, Vec<String>), pest::error::Error<Rule>> {
     // Generate pair for the regex
-    let mut pairs = RegEx::parse(Rule::Regex, regex_input).unwrap_or_else(|e| panic!("{}", e));
-    // println!("Hello: {:#?}", pairs);
+    let mut pairs = RegEx::parse(Rule::Regex, regex_input)?;
     let mut pairs1 = pairs.clone();
 
     let mut all_state_labels: Vec<String> = Vec::new();
-
     getStateLabels(&pairs1.next().unwrap(), &mut all_state_labels);
-    // print!("State Labels: {{ 1: {}", all_state_labels[0]);
-    // for i in 1..all_state_labels.len() {
-    //     print!(", {}: {}", i+1, all_state_labels[i]);
-    // }
-    // println!("}}");
-
-    println!("State Labels: {:?}", all_state_labels);
-
 
     // Parse the pair to an AST
     let x = parse_to_AST(&pairs.next().unwrap());
+    Ok((x, all_state_labels))
+}
 
-    // println!("Hello: {:#?}", x);
-    
-    // println!("Test starts");
-    // Comment from here
-    let mut cnt = 1;
-    let a = augment(&x, &mut cnt); 
-    // println!("Test Ends");
+// Checks whether an AST uses intersection or complement, which have no Glushkov automaton
+fn isExtended(regexp: &Rc<Regex>) -> bool {
+    match regexp.deref() {
+        And(_, _) | Not(_) => true,
+        Or(r1, r2) | Concat(r1, r2) => isExtended(r1) || isExtended(r2),
+        Star(r1) => isExtended(r1),
+        _ => false
+    }
+}
+
+// Error for patterns that need the DFA based modes
+fn extendedError(regex_input: &str) -> pest::error::Error<Rule> {
+    pest::error::Error::new_from_pos(
+        pest::error::ErrorVariant::CustomError {
+            message: "& and ~ are only supported when matching through the DFA".to_string()
+        },
+        pest::Position::from_start(regex_input)
+    )
+}
+
+// Compiles a regular expression into the Glushkov automaton of its simplified AST
+fn compile(regex_input: &str) -> Result<Glushkov, pest::error::Error<Rule>> {
+    let (x, all_state_labels) = parse(regex_input)?;
+    if isExtended(&x) {
+        return Err(extendedError(regex_input));
+    }
+    Ok(buildSimplified(x, all_state_labels))
+}
 
+// Compiles a regular expression into the Glushkov automaton of its AST as parsed
+fn compile_unsimplified(regex_input: &str) -> Result<Glushkov, pest::error::Error<Rule>> {
+    let (x, all_state_labels) = parse(regex_input)?;
+    if isExtended(&x) {
+        return Err(extendedError(regex_input));
+    }
+    Ok(build(x, all_state_labels))
+}
+
+// Simplifies an AST before building its Glushkov automaton. The labels written in the pattern
+// only line up with the positions when simplify leaves the AST as it is
+fn buildSimplified(x: Rc<Regex>, all_state_labels: Vec<String>) -> Glushkov {
+    let y = simplify::simplify(&x);
+    if y == x {
+        build(x, all_state_labels)
+    } else {
+        fromAST(y)
+    }
+}
+
+// Builds the Glushkov automaton of an AST that did not come from a pattern, labelling positions from the AST itself
+fn fromAST(x: Rc<Regex>) -> Glushkov {
+    let mut all_state_labels: Vec<String> = Vec::new();
+    addlabels(&x, &mut all_state_labels);
+    build(x, all_state_labels)
+}
+
+// Builds the Glushkov automaton of an AST whose positions have the given labels
+fn build(x: Rc<Regex>, all_state_labels: Vec<String>) -> Glushkov {
+    let mut cnt = 1;
+    let a = augment(&x, &mut cnt);
     let no_of_states = cnt;
 
-    println!("Augmented enum form: {:?}", a);
-    
     // Generate P, D, F sets
     let P_set = constructP(&a);
     let D_set = constructD(&a);
@@ -820,34 +1035,469 @@ fn main() {
     let mut state_letter: Vec<Vec<bool>> = Vec::new();
     addstates(&x, &mut state_letter);
 
-    
-    // // Generating the NFA in the form of Adjacency Matrix
+    // Generating the NFA in the form of Adjacency Matrix
     let mut array: Vec<Vec<u8>> = vec![Vec::new(); no_of_states.into()];
     for x in &P_set {
-        array[0].push(x.clone());
+        array[0].push(*x);
     }
     for x in &F_set {
         array[x.0 as usize].push(x.1);
     }
-    
-    println!("Pset = {:?}", P_set);
-    println!("Dset = {:?}", D_set);
-    println!("Fset = {:?}", F_set);
-    println!("Number of States = {}", no_of_states);
-    println!("NFA Adjacency List : {:?}", array);
-
-    // for i in 0..no_of_states-1 {
-    //     println!("Size[{}] : {}", i, state_letter[i as usize].len());
-    // }
-    
+    for x in array.iter_mut() {
+        x.sort();
+    }
 
-    if s.len() == 0 && matches!(findLambda(&a).deref(), Eps()) {
+    let nullable = matches!(findLambda(&a).deref(), Eps());
+
+    Glushkov {
+        ast: x,
+        augmented: a,
+        state_labels: all_state_labels,
+        no_of_states,
+        P_set,
+        D_set,
+        F_set,
+        nfa: array,
+        state_letter,
+        nullable
+    }
+}
+
+// Quotes a string of bytes for printing, escaping the unprintable ones
+fn quoted(s: &[u8]) -> String {
+    let res: String = s.iter().map(|x| explain::show_byte(*x)).collect();
+    format!("\"{}\"", res)
+}
+
+// Prints a prompt and reads one trimmed line from standard input
+fn readInput(prompt: &str) -> String {
+    println!("{}", prompt);
+    let mut tmp = String::new();
+    io::stdin().read_line(&mut tmp).expect("failed to readline");
+    tmp.trim().to_string()
+}
+
+fn main() {
+
+    // Optional mode selecting what is printed, see README
+    let mode = std::env::args().nth(1).unwrap_or_default();
+
+    let tmp1 = readInput("Enter a RegEx:");
+    let regex_input = tmp1.as_str();
+    // let regex_input = "[abc-z0-1]{,1}";
+    // let regex_input = "bbc[^5-9]*[abc-z0-1]{3, 4}";
+    // let regex_input = "bbc[^5-9]*[abc-z0-1]{3, 4}3?dd";
+    // let regex_input = "[abc-z0-1]{,1}";
+
+    // Modes that only need the pattern
+    match mode.as_str() {
+        "json" => {
+            let g = compile(regex_input).unwrap_or_else(|e| panic!("{}", e));
+            println!("{}", g.to_json());
+            return;
+        },
+        "simplify" => {
+            let (x, _) = parse(regex_input).unwrap_or_else(|e| panic!("{}", e));
+            println!("Pattern: {}", x);
+            println!("Simplified: {}", simplify::simplify(&x));
+            if !isExtended(&x) {
+                let g = compile_unsimplified(regex_input).unwrap_or_else(|e| panic!("{}", e));
+                let h = compile(regex_input).unwrap_or_else(|e| panic!("{}", e));
+                println!("Number of States = {} -> {}", g.no_of_states, h.no_of_states);
+            }
+            return;
+        },
+        "equiv" | "subset" => {
+            let tmp2 = readInput("Enter another RegEx:");
+            let res = if mode == "equiv" {
+                equiv::equivalent(regex_input, &tmp2)
+            } else {
+                equiv::is_subset(regex_input, &tmp2)
+            };
+            match res.unwrap_or_else(|e| panic!("{}", e)) {
+                equiv::Comparison::Holds if mode == "equiv" => println!("Equivalent"),
+                equiv::Comparison::Holds => println!("Subset"),
+                equiv::Comparison::Fails(w) if mode == "equiv" => println!("Not equivalent, distinguished by {}", quoted(&w)),
+                equiv::Comparison::Fails(w) => println!("Not a subset, {} is only matched by the first", quoted(&w))
+            }
+            return;
+        },
+        "example" => {
+            let (x, _) = parse(regex_input).unwrap_or_else(|e| panic!("{}", e));
+            let (empty, w) = if isExtended(&x) {
+                let d = dfa::fromRegex(&x);
+                (d.is_empty(), d.shortest_match_example())
+            } else {
+                let g = compile(regex_input).unwrap_or_else(|e| panic!("{}", e));
+                (g.is_empty(), g.shortest_match_example())
+            };
+            if empty {
+                println!("Matches nothing");
+            } else {
+                println!("Shortest match: {}", quoted(&w.unwrap()));
+            }
+            return;
+        },
+        "enumerate" => {
+            // Further arguments: how many strings to print (20 by default) and their maximum length
+            let max_count = std::env::args().nth(2).map_or(20, |x| x.parse::<usize>().expect("count must be a number"));
+            let max_len = std::env::args().nth(3).map(|x| x.parse::<usize>().expect("length must be a number"));
+            let g = compile(regex_input).unwrap_or_else(|e| panic!("{}", e));
+            for w in g.enumerate(max_len, Some(max_count)) {
+                println!("{}", quoted(&w));
+            }
+            return;
+        },
+        "sample" => {
+            // Further arguments: the length of the strings and how many to draw (10 by default);
+            // without a length the strings come from a random walk instead
+            let length = std::env::args().nth(2).map(|x| x.parse::<usize>().expect("length must be a number"));
+            let count = std::env::args().nth(3).map_or(10, |x| x.parse::<usize>().expect("count must be a number"));
+            let d = dfa::compile(regex_input).unwrap_or_else(|e| panic!("{}", e)).minimise();
+            let mut rng = rand::thread_rng();
+            for _ in 0..count {
+                let w = match length {
+                    Some(length) => d.sample(length, &mut rng),
+                    None => d.random_walk(0.25, 64, &mut rng)
+                };
+                match w {
+                    Some(w) => println!("{}", quoted(&w)),
+                    None => {
+                        println!("No matching string");
+                        break;
+                    }
+                }
+            }
+            return;
+        },
+        "count" => {
+            // Further argument: the greatest length to count strings of (10 by default)
+            let max_len = std::env::args().nth(2).map_or(10, |x| x.parse::<usize>().expect("length must be a number"));
+            let d = dfa::compile(regex_input).unwrap_or_else(|e| panic!("{}", e)).minimise();
+            // Counts stop at u128::MAX
+            let show = |x: u128| if x == u128::MAX { format!("at least {}", x) } else { x.to_string() };
+            for n in 0..=max_len {
+                println!("Length {}: {}", n, show(d.count(n)));
+            }
+            match d.cardinality() {
+                Some(total) => println!("Finite, {} strings in total", show(total)),
+                None => println!("Infinite")
+            }
+            return;
+        },
+        "oracle" => {
+            // The derivative DFA is built independently of the Glushkov construction
+            let (x, _) = parse(regex_input).unwrap_or_else(|e| panic!("{}", e));
+            let d1 = dfa::fromRegex(&x);
+            let d2 = derivative::toDfa(&x);
+            println!("Number of DFA States = {} (Glushkov), {} (derivatives)", d1.no_of_states(), d2.no_of_states());
+            match equiv::equivalentDfa(&d1, &d2) {
+                equiv::Comparison::Holds => println!("Engines agree"),
+                equiv::Comparison::Fails(w) => println!("Engines disagree on {}", quoted(&w))
+            }
+            return;
+        },
+        "compare" => {
+            // Size of every construction, and of the DFA obtained from it
+            for (name, construction) in automaton::CONSTRUCTIONS {
+                let a = automaton::construct(regex_input, construction).unwrap_or_else(|e| panic!("{}", e));
+                let d = a.determinise();
+                println!("{}: {} states, {} transitions, {} ε-transitions, {} DFA states over {} byte classes", name, a.no_of_states(), a.no_of_transitions(), a.no_of_eps_transitions(), d.no_of_states(), d.no_of_classes());
+            }
+            return;
+        },
+        "dot-nfa" => {
+            // Further argument: the construction, see compare
+            let name = std::env::args().nth(2).unwrap_or("glushkov".to_string());
+            let construction = automaton::CONSTRUCTIONS.iter().find(|(x, _)| *x == name).expect("unknown construction").1;
+            let a = automaton::construct(regex_input, construction).unwrap_or_else(|e| panic!("{}", e));
+            print!("{}", a.to_dot());
+            return;
+        },
+        "to-regex" => {
+            // Further argument: the construction, see compare, or dfa for the minimal DFA,
+            // which also takes patterns with & and ~
+            let name = std::env::args().nth(2).unwrap_or("dfa".to_string());
+            let r = if name == "dfa" {
+                dfa::compile(regex_input).unwrap_or_else(|e| panic!("{}", e)).minimise().to_regex()
+            } else {
+                let construction = automaton::CONSTRUCTIONS.iter().find(|(x, _)| *x == name).expect("unknown construction").1;
+                automaton::construct(regex_input, construction).unwrap_or_else(|e| panic!("{}", e)).to_regex()
+            };
+            println!("{}", r);
+            return;
+        },
+        "deterministic" => {
+            // Checked as written, ab|ac is not deterministic although its simplified form a[bc] is
+            let g = compile_unsimplified(regex_input).unwrap_or_else(|e| panic!("{}", e));
+            if g.is_deterministic() {
+                println!("Deterministic");
+            } else {
+                println!("Not deterministic: {}", g.describe_conflict(&g.conflict().unwrap()));
+            }
+            return;
+        },
+        "redos" => {
+            // Analysed as written, simplify would remove the ambiguity of (a|a)*
+            let g = compile_unsimplified(regex_input).unwrap_or_else(|e| panic!("{}", e));
+            let (kind, a) = match g.backtracking() {
+                redos::Backtracking::Linear => {
+                    println!("No exponential or polynomial backtracking");
+                    return;
+                },
+                redos::Backtracking::Exponential(a) => ("Exponential", a),
+                redos::Backtracking::Polynomial(a) => ("Polynomial", a)
+            };
+            println!("{} backtracking in {}", kind, a.subexpression);
+            match a.suffix {
+                Some(suffix) => println!("Attack: prefix {}, pump {} repeated, suffix {}", quoted(&a.prefix), quoted(&a.pump), quoted(&suffix)),
+                None => println!("Not exploitable: every string starting with prefix {} and pump {} repeated is matched", quoted(&a.prefix), quoted(&a.pump))
+            }
+            return;
+        },
+        "ambiguity" => {
+            let g = compile_unsimplified(regex_input).unwrap_or_else(|e| panic!("{}", e));
+            if !g.is_ambiguous() {
+                println!("Unambiguous");
+                return;
+            }
+            let a = g.ambiguity().unwrap();
+            println!("Ambiguous: {} has two accepting paths", quoted(&a.witness));
+            println!("  {}", g.describe_path(&a.first));
+            println!("  {}", g.describe_path(&a.second));
+            return;
+        },
+        "literals" => {
+            let (x, _) = parse(regex_input).unwrap_or_else(|e| panic!("{}", e));
+            let info = match literals::analyse(&x) {
+                Some(info) => info,
+                None => {
+                    println!("Matches nothing");
+                    return;
+                }
+            };
+            let show = |set: &literals::Strings| set.iter().map(|w| quoted(w)).collect::<Vec<String>>().join(", ");
+            match info.max {
+                Some(max) => println!("Length: {} to {}", info.min, max),
+                None => println!("Length: at least {}", info.min)
+            }
+            if let Some(exact) = &info.exact {
+                println!("Exactly: {}", show(exact));
+            }
+            println!("Prefixes: {}", show(&info.prefixes));
+            println!("Suffixes: {}", show(&info.suffixes));
+            println!("Required: {}", show(&info.required));
+            return;
+        },
+        "trigrams" => {
+            let (x, _) = parse(regex_input).unwrap_or_else(|e| panic!("{}", e));
+            println!("{}", trigram::trigram_query(&x));
+            return;
+        },
+        "reverse" => {
+            let (x, _) = parse(regex_input).unwrap_or_else(|e| panic!("{}", e));
+            println!("{}", reverse::reverse(&x));
+            return;
+        },
+        "complement" => {
+            let (x, _) = parse(regex_input).unwrap_or_else(|e| panic!("{}", e));
+            println!("{}", dfa::fromRegex(&simplify::mkNot(&x)).minimise().to_regex());
+            return;
+        },
+        "symbolic" | "symbolic-and" | "dot-symbolic" => {
+            // symbolic-and reads a second RegEx and works on the intersection of both
+            let g = compile(regex_input).unwrap_or_else(|e| panic!("{}", e));
+            let mut a = g.to_sfa().determinise();
+            let mut d = dfa::determinise(&g);
+            if mode == "symbolic-and" {
+                let tmp2 = readInput("Enter another RegEx:");
+                let h = compile(&tmp2).unwrap_or_else(|e| panic!("{}", e));
+                a = symbolic::intersection(&a, &h.to_sfa().determinise());
+                d = dfa::product(&d, &dfa::determinise(&h), |x, y| x && y);
+            }
+            let m = a.minimise();
+            if mode == "dot-symbolic" {
+                print!("{}", m.to_dot());
+                return;
+            }
+            let terms = symbolic::minterms(m.edges.iter().flatten().map(|(p, _)| p));
+            println!("Deterministic: {} states, {} transitions", a.no_of_states(), a.no_of_transitions());
+            println!("Minimal: {} states, {} transitions, {} minterms", m.no_of_states(), m.no_of_transitions(), terms.len());
+            println!("Minimal DFA over byte classes: {} states", d.minimise().no_of_states());
+            return;
+        },
+        "dot" => {
+            let g = compile(regex_input).unwrap_or_else(|e| panic!("{}", e));
+            print!("{}", g.to_dot());
+            return;
+        },
+        "dot-dfa" | "dot-min" => {
+            let mut d = dfa::compile(regex_input).unwrap_or_else(|e| panic!("{}", e));
+            if mode == "dot-min" {
+                d = d.minimise();
+            }
+            print!("{}", d.to_dot());
+            return;
+        },
+        _ => {}
+    }
+
+    let tmp2 = readInput("Enter a string:");
+    let s: &str = tmp2.as_str();
+    // let s: &str = "bbc333mn03dd";
+
+    match mode.as_str() {
+        "match" | "derivative" => {
+            let engine = if mode == "derivative" { engine::Engine::Derivative } else { engine::Engine::Glushkov };
+            let mut m = engine::Matcher::new(regex_input, engine).unwrap_or_else(|e| panic!("{}", e));
+            if m.is_match(s.as_bytes()) {
+                println!("Accepted");
+            } else {
+                println!("Rejected");
+            }
+            return;
+        },
+        "find" | "rfind" => {
+            let m = if mode == "find" {
+                prefilter::Searcher::new(regex_input).unwrap_or_else(|e| panic!("{}", e)).find(s.as_bytes())
+            } else {
+                compile(regex_input).unwrap_or_else(|e| panic!("{}", e)).rfind(s.as_bytes())
+            };
+            match m {
+                Some((start, end)) => println!("Match at {}..{}: {}", start, end, quoted(&s.as_bytes()[start..end])),
+                None => println!("No match")
+            }
+            return;
+        },
+        "explain" | "explain-json" => {
+            let e = explain::explain(regex_input, s).unwrap_or_else(|e| panic!("{}", e));
+            if mode == "explain" {
+                print!("{}", e.to_table());
+            } else {
+                println!("{}", e.to_json());
+            }
+            return;
+        },
+        _ => {}
+    }
+
+    // Patterns with & or ~ are matched on their DFA
+    if mode != "match-json" {
+        let (x, _) = parse(regex_input).unwrap_or_else(|e| panic!("{}", e));
+        if isExtended(&x) {
+            let d = dfa::fromRegex(&x).minimise();
+            println!("AST as pattern: {}", x);
+            println!("Number of DFA States = {}", d.no_of_states());
+            if d.accepts(s.as_bytes()) {
+                println!("Accepted");
+            } else {
+                println!("Rejected");
+            }
+            return;
+        }
+    }
+
+    // In "match-json" mode the first line is an automaton exported by the "json" mode instead of a RegEx
+    let g = if mode == "match-json" {
+        Glushkov::from_json(regex_input).unwrap_or_else(|e| panic!("{}", e))
+    } else {
+        compile(regex_input).unwrap_or_else(|e| panic!("{}", e))
+    };
+
+    println!("State Labels: {:?}", g.state_labels);
+    println!("AST as pattern: {}", g.ast);
+    println!("Augmented enum form: {:?}", g.augmented);
+    println!("Pset = {:?}", g.P_set);
+    println!("Dset = {:?}", g.D_set);
+    println!("Fset = {:?}", g.F_set);
+    println!("Number of States = {}", g.no_of_states);
+    println!("NFA Adjacency List : {:?}", g.nfa);
+
+    if s.is_empty() && g.nullable {
         println!("Accepted");
+        return;
     }
-    let res = checkstr(s, &array, &D_set, 0, 0, &state_letter);
+    let res = checkstr(s, &g.nfa, &g.D_set, 0, 0, &g.state_letter);
     if res {
         println!("Accepted");
     } else {
         println!("Rejected");
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    // Every string over the alphabet of length at most max_len, shortest first
+    pub fn all_strings(alphabet: &[u8], max_len: usize) -> Vec<Vec<u8>> {
+        let mut res: Vec<Vec<u8>> = vec![Vec::new()];
+        let mut last: Vec<Vec<u8>> = vec![Vec::new()];
+        for _ in 0..max_len {
+            let mut next: Vec<Vec<u8>> = Vec::new();
+            for s in &last {
+                for a in alphabet {
+                    let mut t = s.clone();
+                    t.push(*a);
+                    next.push(t);
+                }
+            }
+            res.extend(next.iter().cloned());
+            last = next;
+        }
+        res
+    }
+
+    #[test]
+    fn deep_nesting_parses_quickly() {
+        let pattern = format!("{}a|b{}", "(".repeat(100), ")".repeat(100));
+        let start = Instant::now();
+        let (x, labels) = parse(&pattern).unwrap();
+        assert!(start.elapsed() < Duration::from_secs(1));
+        assert_eq!(x, Rc::new(Or(Rc::new(Letter(b'a')), Rc::new(Letter(b'b')))));
+        assert_eq!(labels, vec!["a", "b"]);
+    }
+
+    #[test]
+    fn operators_nest_to_the_right() {
+        let a = Rc::new(Letter(b'a'));
+        let b = Rc::new(Letter(b'b'));
+        let c = Rc::new(Letter(b'c'));
+        assert_eq!(parse("a|b|c").unwrap().0, Rc::new(Or(a.clone(), Rc::new(Or(b.clone(), c.clone())))));
+        assert_eq!(parse("abc").unwrap().0, Rc::new(Concat(a.clone(), Rc::new(Concat(b.clone(), c.clone())))));
+        assert_eq!(parse("a&b&c").unwrap().0, Rc::new(And(a.clone(), Rc::new(And(b.clone(), c.clone())))));
+        assert_eq!(parse("ab|c&a").unwrap().0,
+            Rc::new(Or(Rc::new(Concat(a.clone(), b.clone())), Rc::new(And(c.clone(), a.clone())))));
+        assert_eq!(parse("~a*").unwrap().0, Rc::new(Not(Rc::new(Star(a.clone())))));
+        assert_eq!(parse("~~ab").unwrap().0, Rc::new(Concat(Rc::new(Not(Rc::new(Not(a.clone())))), b.clone())));
+    }
+
+    // Every position of the augmented AST gets the label of the pattern text it came from
+    #[test]
+    fn labels_line_up_with_positions() {
+        let cases = [
+            ("a+", vec!["a", "a"]),
+            ("(ab)?c", vec!["a", "b", "c"]),
+            ("[a-c]{2,3}", vec!["[a-c]", "[a-c]", "[a-c]"]),
+            ("(a|b){2,}", vec!["a", "b", "a", "b", "a", "b"]),
+            ("a{,2}", vec!["a", "a"]),
+            ("(ab){2}.", vec!["a", "b", "a", "b", "."]),
+            ("a{0}b", vec!["b"]),
+            ("()\\x41", vec!["\\x41"]),
+        ];
+        for (pattern, labels) in cases {
+            let g = compile_unsimplified(pattern).unwrap();
+            assert_eq!(g.state_labels, labels, "{}", pattern);
+            assert_eq!(g.state_labels.len() + 1, g.no_of_states as usize, "{}", pattern);
+        }
+    }
+
+    #[test]
+    fn analyses_see_the_pattern_as_written() {
+        assert!(!compile_unsimplified("ab|ac").unwrap().is_deterministic());
+        assert!(compile("ab|ac").unwrap().is_deterministic());
+        assert!(compile_unsimplified("(a|a)*b").unwrap().is_ambiguous());
+        assert!(!compile("(a|a)*b").unwrap().is_ambiguous());
+    }
+}
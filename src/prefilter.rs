@@ -0,0 +1,128 @@
+// Search sped up by the literals every match starts with or contains: candidate starts are
+// found with a substring search, and only there is the automaton run. Patterns that are just a
+// set of literals are searched with an Aho–Corasick automaton instead
+
+use memchr::memmem::Finder;
+
+use crate::ahocorasick::{literal_set, AhoCorasick};
+use crate::literals::analyse;
+use crate::{compile, Glushkov, Rule};
+
+pub struct Searcher {
+    g: Glushkov,
+    // Every match starts with one of these, none of them empty
+    prefixes: Vec<Finder<'static>>,
+    // Every match contains this string
+    required: Option<Finder<'static>>,
+    keywords: Option<AhoCorasick>
+}
+
+impl Searcher {
+    pub fn new(regex_input: &str) -> Result<Searcher, pest::error::Error<Rule>> {
+        let g = compile(regex_input)?;
+        let mut prefixes: Vec<Finder<'static>> = Vec::new();
+        let mut required: Option<Finder<'static>> = None;
+        if let Some(info) = analyse(&g.ast) {
+            if info.prefixes.iter().all(|w| !w.is_empty()) {
+                prefixes = info.prefixes.iter().map(|w| Finder::new(w).into_owned()).collect();
+            }
+            required = info.required.iter().max_by_key(|w| w.len()).map(|w| Finder::new(w).into_owned());
+        }
+        let keywords = literal_set(&g.ast).map(|k| AhoCorasick::new(&k));
+        Ok(Searcher { g, prefixes, required, keywords })
+    }
+
+    // Positions a match may start at, in increasing order: the occurrences of the prefixes, as
+    // long as the required string still occurs from there
+    fn candidates<'a>(&'a self, s: &'a [u8]) -> impl Iterator<Item = usize> + 'a {
+        let mut from = 0;
+        let mut required_at: Option<usize> = None;
+        std::iter::from_fn(move || {
+            let start = self.prefixes.iter().filter_map(|f| f.find(&s[from..])).min()? + from;
+            if let Some(r) = &self.required {
+                if required_at.is_none_or(|x| x < start) {
+                    required_at = Some(r.find(&s[start..])? + start);
+                }
+            }
+            from = start + 1;
+            Some(start)
+        })
+    }
+
+    // Leftmost longest match. Every match starts at an occurrence of a prefix, so the automaton
+    // only runs forward from each candidate in turn, and the first one it matches from is the
+    // leftmost; without prefixes the required string decides whether to search at all
+    pub fn find(&self, s: &[u8]) -> Option<(usize, usize)> {
+        if let Some(a) = &self.keywords {
+            return a.find(s);
+        }
+        if self.prefixes.is_empty() {
+            if let Some(r) = &self.required {
+                r.find(s)?;
+            }
+            return self.g.find(s);
+        }
+        self.candidates(s).find_map(|start| self.g.longest_from(s, start).map(|end| (start, end)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::all_strings;
+
+    // Same pattern searched without any of the literals
+    fn plain(regex_input: &str) -> Searcher {
+        Searcher { g: compile(regex_input).unwrap(), prefixes: Vec::new(), required: None, keywords: None }
+    }
+
+    #[test]
+    fn same_matches_with_and_without_literals() {
+        let patterns = [
+            "[a-z]bcd|c", "ab|b", "abc|bcd", "b(a|c)*", "(ab|ba)c+", "c[ab]*a", "a*bc", "(a|b)*cab",
+            "ab|abc|bcc", "cc|a", "abba",
+        ];
+        let strings = all_strings(b"abc", 6);
+        for pattern in patterns {
+            let fast = Searcher::new(pattern).unwrap();
+            let slow = plain(pattern);
+            for s in &strings {
+                assert_eq!(fast.find(s), slow.find(s), "{} on {:?}", pattern, s);
+            }
+        }
+    }
+
+    // The automaton is only run from the occurrences of the prefixes, and not from any after the
+    // first one it matches from
+    #[test]
+    fn verified_only_at_candidates() {
+        let f = Searcher::new("b(a|c)*d").unwrap();
+        let mut s = vec![b'x'; 10000];
+        s[100] = b'b';
+        s[200] = b'b';
+        s[201] = b'd';
+        s[5000] = b'b';
+        s[5001] = b'd';
+        assert_eq!(f.candidates(&s).collect::<Vec<usize>>(), vec![100, 200, 5000]);
+        let mut verified = 0;
+        let found = f.candidates(&s).find_map(|start| {
+            verified += 1;
+            f.g.longest_from(&s, start).map(|end| (start, end))
+        });
+        assert_eq!(found, Some((200, 202)));
+        assert_eq!(found, f.find(&s));
+        assert_eq!(verified, 2);
+        // No candidate is left once the required string no longer occurs after it
+        let f = Searcher::new("b(a|c)*cab").unwrap();
+        assert_eq!(f.candidates(b"bxxcabxxbxxbxx").collect::<Vec<usize>>(), vec![0]);
+    }
+
+    #[test]
+    fn literals_are_used() {
+        assert!(!Searcher::new("b(a|c)*").unwrap().prefixes.is_empty());
+        assert!(Searcher::new("(a|b)*cab").unwrap().required.is_some());
+        assert!(Searcher::new("ab|abc|bcc").unwrap().keywords.is_some());
+        assert_eq!(Searcher::new("[a-z]bcd|c").unwrap().find(b"xxbcd"), Some((1, 5)));
+        assert_eq!(Searcher::new("b(a|c)*").unwrap().find(b"aacbcab"), Some((3, 6)));
+    }
+}
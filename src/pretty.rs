@@ -0,0 +1,165 @@
+// Printing a Regex AST back in pattern syntax
+
+use std::fmt;
+use std::ops::Deref;
+
+use crate::Regex;
+use crate::Regex::{Empty, Eps, Letter, CharClass, Or, Concat, Star, And, Not};
+use crate::{classLabel, letterLabel};
+
+// Binding strength of the printed forms, an operand weaker than its context gets parentheses
+const ALT: u8 = 0;
+const INTERSECT: u8 = 1;
+const SEQ: u8 = 2;
+const POSTFIX: u8 = 3;
+const ATOM: u8 = 4;
+
+// Checks for the Concat(r, Star(r)) expansion of r+
+fn plus_operand(regexp: &Regex) -> Option<&Regex> {
+    match regexp {
+        Concat(r1, r2) => match r2.deref() {
+            Star(r3) if r1 == r3 => Some(r1.deref()),
+            _ => None
+        },
+        _ => None
+    }
+}
+
+// Checks for the Or(r, Eps) expansion of r?
+fn optional_operand(regexp: &Regex) -> Option<&Regex> {
+    match regexp {
+        Or(r1, r2) if matches!(r2.deref(), Eps()) => Some(r1.deref()),
+        Or(r1, r2) if matches!(r1.deref(), Eps()) => Some(r2.deref()),
+        _ => None
+    }
+}
+
+fn strength(regexp: &Regex) -> u8 {
+    if plus_operand(regexp).is_some() || optional_operand(regexp).is_some() {
+        return POSTFIX;
+    }
+    match regexp {
+        Or(_, _) => ALT,
+        And(_, _) => INTERSECT,
+        Concat(_, _) => SEQ,
+        Star(_) | Not(_) => POSTFIX,
+        _ => ATOM
+    }
+}
+
+// Writes an operand, in parentheses when it binds weaker than its context requires
+fn write_operand(f: &mut fmt::Formatter, regexp: &Regex, context: u8) -> fmt::Result {
+    if strength(regexp) < context {
+        write!(f, "(")?;
+        write_regex(f, regexp)?;
+        write!(f, ")")
+    } else {
+        write_regex(f, regexp)
+    }
+}
+
+fn write_regex(f: &mut fmt::Formatter, regexp: &Regex) -> fmt::Result {
+    if let Some(r) = plus_operand(regexp) {
+        write_operand(f, r, ATOM)?;
+        return write!(f, "+");
+    }
+    if let Some(r) = optional_operand(regexp) {
+        write_operand(f, r, ATOM)?;
+        return write!(f, "?");
+    }
+    match regexp {
+        // No pattern syntax for the empty language, a class with no bytes in it is used instead
+        Empty() => write!(f, "{}", classLabel(&[false; 256])),
+        Eps() => write!(f, "()"),
+        Letter(a) => write!(f, "{}", letterLabel(*a)),
+        CharClass(charvec) => write!(f, "{}", classLabel(charvec)),
+        Or(r1, r2) => {
+            write_operand(f, r1, ALT)?;
+            write!(f, "|")?;
+            write_operand(f, r2, ALT)
+        },
+        Concat(r1, r2) => {
+            write_operand(f, r1, SEQ)?;
+            write_operand(f, r2, SEQ)
+        },
+        Star(r1) => {
+            write_operand(f, r1, ATOM)?;
+            write!(f, "*")
+        },
+        And(r1, r2) => {
+            write_operand(f, r1, INTERSECT)?;
+            write!(f, "&")?;
+            write_operand(f, r2, INTERSECT)
+        },
+        Not(r1) => {
+            write!(f, "~")?;
+            write_operand(f, r1, POSTFIX)
+        }
+    }
+}
+
+// Emits the expression with the fewest parentheses the grammar needs,
+// writing the expansions of + and ? back in their short form
+impl fmt::Display for Regex {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_regex(f, self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use crate::parse;
+    use crate::Regex;
+    use crate::Regex::{Empty, Eps, Letter, Or, Concat, Star};
+
+    fn printed(pattern: &str) -> String {
+        parse(pattern).unwrap().0.to_string()
+    }
+
+    // Printing, parsing the printed form and printing again gives the same text
+    fn assert_round_trip(regexp: &Rc<Regex>) {
+        let once = regexp.to_string();
+        let (reparsed, _) = parse(&once).unwrap_or_else(|e| panic!("{} does not parse: {}", once, e));
+        assert_eq!(reparsed.to_string(), once);
+    }
+
+    #[test]
+    fn round_trip() {
+        let patterns = [
+            "a", "ab", "a|b", "(a|b)c", "a(b|c)*", "(ab)*", "(ab)+", "(a|b)?", "a+b?c*",
+            "[a-z]", "[^a-c0-9]", "([ab]|c)d", "([ab]c)*", "[ab]([cd]|e)", "a{2,3}", "a{2,}", "a{,2}", "(ab){3}",
+            "()", "(()|a)b", ".", "\\x00\\x7f", "~a", "~(ab)", "a&b*", "(a|b)&~(ab)", "(a&b)*",
+        ];
+        for pattern in patterns {
+            assert_round_trip(&parse(pattern).unwrap().0);
+        }
+    }
+
+    #[test]
+    fn round_trip_without_pattern_syntax() {
+        let a = Rc::new(Letter(b'a'));
+        let b = Rc::new(Letter(b'b'));
+        let trees = [
+            Rc::new(Empty()),
+            Rc::new(Eps()),
+            Rc::new(Concat(Rc::new(Or(a.clone(), b.clone())), Rc::new(Empty()))),
+            Rc::new(Or(Rc::new(Or(a.clone(), b.clone())), Rc::new(Eps()))),
+            Rc::new(Star(Rc::new(Star(a.clone())))),
+            Rc::new(Concat(Rc::new(Concat(a.clone(), b.clone())), a.clone())),
+        ];
+        for t in &trees {
+            assert_round_trip(t);
+        }
+    }
+
+    #[test]
+    fn short_forms() {
+        assert_eq!(printed("(a)"), "a");
+        assert_eq!(printed("((a|b))c"), "(a|b)c");
+        assert_eq!(printed("a+"), "a+");
+        assert_eq!(printed("(ab)?"), "(ab)?");
+        assert_eq!(printed("a|(b|c)"), "a|b|c");
+    }
+}
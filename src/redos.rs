@@ -0,0 +1,361 @@
+// Static analysis of the backtracking behaviour of a pattern on engines such as JavaScript's or
+// PCRE's: a state of the Glushkov automaton with two different loops on the same string (EDA)
+// makes them take exponential time on failing inputs, and two states with a string looping on
+// the first, leading to the second and looping on the second (IDA) polynomial time. Loops
+// reading nothing, as in (a*)*, have no positions of their own and are not seen
+
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+use std::ops::Deref;
+use std::rc::Rc;
+
+use crate::classes::representatives;
+use crate::dfa::determinise;
+use crate::{Glushkov, Regex};
+use crate::Regex::{Letter, Or, Concat, Star};
+
+// Breadth-first search from a key to the nearest key satisfying the goal, after at least one
+// byte so that the start itself can be the goal; returns the path as the bytes read, each with
+// the key it leads to
+pub fn shortest_path<K, N, G>(start: K, next: N, goal: G) -> Option<Vec<(u8, K)>>
+where
+    K: Clone + Eq + Hash,
+    N: Fn(&K) -> Vec<(u8, K)>,
+    G: Fn(&K) -> bool
+{
+    let mut prev: HashMap<K, Option<(K, u8)>> = HashMap::new();
+    let mut queue: VecDeque<K> = VecDeque::new();
+    prev.insert(start.clone(), None);
+    queue.push_back(start);
+    while let Some(curr) = queue.pop_front() {
+        for (byte, k) in next(&curr) {
+            if goal(&k) {
+                let mut res: Vec<(u8, K)> = vec![(byte, k)];
+                let mut at = curr.clone();
+                while let Some(Some((from, byte))) = prev.get(&at) {
+                    res.push((*byte, at.clone()));
+                    at = from.clone();
+                }
+                res.reverse();
+                return Some(res);
+            }
+            if !prev.contains_key(&k) {
+                prev.insert(k.clone(), Some((curr.clone(), byte)));
+                queue.push_back(k);
+            }
+        }
+    }
+    None
+}
+
+// Bytes read along a path
+pub fn path_bytes<K>(path: &[(u8, K)]) -> Vec<u8> {
+    path.iter().map(|(byte, _)| *byte).collect()
+}
+
+// Strings pumping a vulnerable subexpression: prefix pump^k suffix with the suffix making the
+// match fail, which is when a backtracking engine tries every way of reading the pumped part
+pub struct Attack {
+    pub subexpression: Rc<Regex>,
+    pub prefix: Vec<u8>,
+    pub pump: Vec<u8>,
+    // None when every string starting with prefix pump^k is matched
+    pub suffix: Option<Vec<u8>>
+}
+
+pub enum Backtracking {
+    Linear,
+    Exponential(Attack),
+    Polynomial(Attack)
+}
+
+// Range of the positions inside an augmented subexpression, which are numbered consecutively
+fn span(augmented: &Rc<Regex>) -> Option<(u8, u8)> {
+    match augmented.deref() {
+        Letter(x) => Some((*x, *x)),
+        Or(r1, r2) | Concat(r1, r2) => match (span(r1), span(r2)) {
+            (Some((lo, _)), Some((_, hi))) => Some((lo, hi)),
+            (s1, s2) => s1.or(s2)
+        },
+        Star(r1) => span(r1),
+        _ => None
+    }
+}
+
+// Innermost subexpression (innermost starred one if asked) whose positions include lo..=hi,
+// walking the augmented AST alongside the original one
+fn locate(ast: &Rc<Regex>, augmented: &Rc<Regex>, lo: u8, hi: u8, star: bool) -> Option<Rc<Regex>> {
+    match span(augmented) {
+        Some((l, h)) if l <= lo && hi <= h => {},
+        _ => return None
+    }
+    let inner = match (ast.deref(), augmented.deref()) {
+        (Or(a1, a2), Or(b1, b2)) | (Concat(a1, a2), Concat(b1, b2)) => {
+            locate(a1, b1, lo, hi, star).or_else(|| locate(a2, b2, lo, hi, star))
+        },
+        (Star(a1), Star(b1)) => locate(a1, b1, lo, hi, star),
+        _ => None
+    };
+    match inner {
+        Some(r) => Some(r),
+        None if !star || matches!(ast.deref(), Star(_)) => Some(ast.clone()),
+        None => None
+    }
+}
+
+impl Glushkov {
+    // Smallest byte that both positions can read, if any
+    fn common_byte(&self, j: u8, k: u8) -> Option<u8> {
+        let c1 = &self.state_letter[(j-1) as usize];
+        let c2 = &self.state_letter[(k-1) as usize];
+        (0..256).find(|x| c1[*x] && c2[*x]).map(|x| x as u8)
+    }
+
+    // Transitions of the product of the automaton with itself
+    pub fn pair_next(&self, (a, b): &(u8, u8)) -> Vec<(u8, (u8, u8))> {
+        let mut res: Vec<(u8, (u8, u8))> = Vec::new();
+        for j in &self.nfa[*a as usize] {
+            for k in &self.nfa[*b as usize] {
+                if let Some(byte) = self.common_byte(*j, *k) {
+                    res.push((byte, (*j, *k)));
+                }
+            }
+        }
+        res
+    }
+
+    // Transitions of the product of three copies of the automaton
+    fn triple_next(&self, (a, b, c): &(u8, u8, u8)) -> Vec<(u8, (u8, u8, u8))> {
+        let mut res: Vec<(u8, (u8, u8, u8))> = Vec::new();
+        for (_, (j, k)) in self.pair_next(&(*a, *b)) {
+            for l in &self.nfa[*c as usize] {
+                let c1 = &self.state_letter[(j-1) as usize];
+                let c2 = &self.state_letter[(k-1) as usize];
+                let c3 = &self.state_letter[(l-1) as usize];
+                if let Some(byte) = (0..256).find(|x| c1[*x] && c2[*x] && c3[*x]) {
+                    res.push((byte as u8, (j, k, *l)));
+                }
+            }
+        }
+        res
+    }
+
+    // Shortest string leading from state 0 to a state, reading the smallest byte of each position
+    fn path_to(&self, state: u8) -> Option<Vec<u8>> {
+        if state == 0 {
+            return Some(Vec::new());
+        }
+        let next = |a: &u8| -> Vec<(u8, u8)> {
+            self.nfa[*a as usize].iter()
+                .filter_map(|j| self.state_letter[(j-1) as usize].iter().position(|x| *x).map(|b| (b as u8, *j)))
+                .collect()
+        };
+        shortest_path(0u8, next, |a| *a == state).map(|p| path_bytes(&p))
+    }
+
+    // Shortest string making the whole match fail after prefix pump^k, whatever k >= 1
+    fn failing_suffix(&self, prefix: &[u8], pump: &[u8]) -> Option<Vec<u8>> {
+        let d = determinise(self);
+        let run = |mut s: usize, w: &[u8]| {
+            for byte in w {
+                s = d.next(s, *byte);
+            }
+            s
+        };
+        // The DFA states after prefix pump^k repeat from some k on
+        let mut states: Vec<usize> = Vec::new();
+        let mut s = run(run(0, prefix), pump);
+        while !states.contains(&s) {
+            states.push(s);
+            s = run(s, pump);
+        }
+        states.sort();
+        states.dedup();
+        if states.iter().all(|s| !d.finals[*s]) {
+            return Some(Vec::new());
+        }
+        let bytes = representatives(&d.classes);
+        let next = |set: &Vec<usize>| -> Vec<(u8, Vec<usize>)> {
+            bytes.iter().map(|&byte| {
+                let mut t: Vec<usize> = set.iter().map(|s| d.next(*s, byte)).collect();
+                t.sort();
+                t.dedup();
+                (byte, t)
+            }).collect()
+        };
+        shortest_path(states, next, |set| set.iter().all(|s| !d.finals[*s])).map(|p| path_bytes(&p))
+    }
+
+    // A state q with two different paths from q back to q reading the same string: the product
+    // automaton goes from (q, q) through a pair of different states and back to (q, q)
+    fn exponential(&self) -> Option<Attack> {
+        let n = self.no_of_states;
+        for q in 1..n {
+            let prefix = match self.path_to(q) {
+                Some(w) => w,
+                None => continue
+            };
+            let back = |pair: &(u8, u8)| -> Option<Vec<u8>> {
+                if *pair == (q, q) {
+                    return Some(Vec::new());
+                }
+                shortest_path(*pair, |p| self.pair_next(p), |p| *p == (q, q)).map(|p| path_bytes(&p))
+            };
+            let found = shortest_path((q, q), |p| self.pair_next(p), |(a, b)| a != b && back(&(*a, *b)).is_some());
+            if let Some(path) = found {
+                let (a, b) = path.last().unwrap().1;
+                let mut pump = path_bytes(&path);
+                pump.extend(back(&(a, b)).unwrap());
+                let lo = q.min(a).min(b);
+                let hi = q.max(a).max(b);
+                return Some(Attack {
+                    subexpression: locate(&self.ast, &self.augmented, lo, hi, true).unwrap_or_else(|| self.ast.clone()),
+                    suffix: self.failing_suffix(&prefix, &pump),
+                    prefix,
+                    pump
+                });
+            }
+        }
+        None
+    }
+
+    // Two different states p and q with a string looping on p, leading from p to q and looping
+    // on q: three copies of the automaton go from (p, p, q) to (p, q, q)
+    fn polynomial(&self) -> Option<Attack> {
+        let n = self.no_of_states;
+        let loops = |p: u8| shortest_path(p, |a| {
+            self.nfa[*a as usize].iter().map(|j| (0u8, *j)).collect()
+        }, |a| *a == p).is_some();
+        let cyclic: Vec<u8> = (1..n).filter(|p| loops(*p)).collect();
+        for p in &cyclic {
+            let prefix = match self.path_to(*p) {
+                Some(w) => w,
+                None => continue
+            };
+            for q in &cyclic {
+                if p == q {
+                    continue;
+                }
+                let found = shortest_path((*p, *p, *q), |t| self.triple_next(t), |t| *t == (*p, *q, *q));
+                if let Some(path) = found {
+                    let pump = path_bytes(&path);
+                    return Some(Attack {
+                        subexpression: locate(&self.ast, &self.augmented, *p.min(q), *p.max(q), false).unwrap_or_else(|| self.ast.clone()),
+                        suffix: self.failing_suffix(&prefix, &pump),
+                        prefix,
+                        pump
+                    });
+                }
+            }
+        }
+        None
+    }
+
+    // Worst case of a backtracking engine on the pattern, with an attack string when not linear
+    pub fn backtracking(&self) -> Backtracking {
+        if let Some(a) = self.exponential() {
+            return Backtracking::Exponential(a);
+        }
+        match self.polynomial() {
+            Some(a) => Backtracking::Polynomial(a),
+            None => Backtracking::Linear
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compile_unsimplified;
+
+    // Number of runs of the automaton on a string, ending in any state
+    fn runs(g: &Glushkov, s: &[u8]) -> u128 {
+        let n = g.no_of_states as usize;
+        let mut count: Vec<u128> = vec![0; n];
+        count[0] = 1;
+        for byte in s {
+            let mut next: Vec<u128> = vec![0; n];
+            for (i, c) in count.iter().enumerate() {
+                for j in &g.nfa[i] {
+                    if g.state_letter[(j-1) as usize][*byte as usize] {
+                        next[*j as usize] = next[*j as usize].saturating_add(*c);
+                    }
+                }
+            }
+            count = next;
+        }
+        count.iter().fold(0, |a, b| a.saturating_add(*b))
+    }
+
+    fn pumped(a: &Attack, k: usize) -> Vec<u8> {
+        let mut s = a.prefix.clone();
+        for _ in 0..k {
+            s.extend(&a.pump);
+        }
+        s
+    }
+
+    // The suffix makes every pumped string fail
+    fn assert_fails(g: &Glushkov, a: &Attack, pattern: &str) {
+        let suffix = a.suffix.as_ref().unwrap_or_else(|| panic!("{}", pattern));
+        for k in 1..6 {
+            let mut s = pumped(a, k);
+            s.extend(suffix);
+            assert!(!g.accepts(&s), "{} on {:?}", pattern, s);
+        }
+    }
+
+    #[test]
+    fn exponential() {
+        for (pattern, sub) in [("(a|a)*b", "(a|a)*"), ("(a+)+b", "(a+)*"), ("x(ab|a|b)*y", "(ab|a|b)*")] {
+            let g = compile_unsimplified(pattern).unwrap();
+            match g.backtracking() {
+                Backtracking::Exponential(a) => {
+                    assert_eq!(a.subexpression.to_string(), sub, "{}", pattern);
+                    // Two loops on the pump at least double the runs every time
+                    for k in 1..6 {
+                        assert!(runs(&g, &pumped(&a, k)) >= 1 << k, "{}", pattern);
+                    }
+                    assert_fails(&g, &a, pattern);
+                },
+                _ => panic!("{} is not exponential", pattern)
+            }
+        }
+    }
+
+    #[test]
+    fn polynomial() {
+        for pattern in ["a*a*b", "x*(x|y)*x*z", "\\x00*\\x00*"] {
+            let g = compile_unsimplified(pattern).unwrap();
+            match g.backtracking() {
+                Backtracking::Polynomial(a) => {
+                    // The runs keep growing with the pump, but not exponentially
+                    for k in 1..6 {
+                        assert!(runs(&g, &pumped(&a, k + 1)) > runs(&g, &pumped(&a, k)), "{}", pattern);
+                    }
+                    if a.suffix.is_some() {
+                        assert_fails(&g, &a, pattern);
+                    }
+                },
+                _ => panic!("{} is not polynomial", pattern)
+            }
+        }
+    }
+
+    #[test]
+    fn linear() {
+        for pattern in ["a*b", "(ab)*", "a*b*", "(a|b)*c", "[ab]*a?", "(a|())b"] {
+            assert!(matches!(compile_unsimplified(pattern).unwrap().backtracking(), Backtracking::Linear), "{}", pattern);
+        }
+    }
+
+    #[test]
+    fn shortest_paths() {
+        // A cycle of length 3 through the start
+        let next = |x: &u8| vec![(*x, (*x + 1) % 3)];
+        let path = shortest_path(0u8, next, |x| *x == 0).unwrap();
+        assert_eq!(path, vec![(0, 1), (1, 2), (2, 0)]);
+        assert_eq!(path_bytes(&path), vec![0, 1, 2]);
+        assert!(shortest_path(0u8, |_| Vec::new(), |_| true).is_none());
+    }
+}
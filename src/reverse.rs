@@ -0,0 +1,219 @@
+// Reversal of patterns and of their Glushkov automata, and searching for matches inside a
+// string with a backward scan for the start of a match and a forward scan for its end
+
+use std::collections::HashSet;
+use std::ops::Deref;
+use std::rc::Rc;
+
+use crate::{Glushkov, Regex};
+use crate::Regex::{Or, Concat, Star, And, Not};
+
+// Expression matching the reversed strings, the operands of every concatenation being swapped
+pub fn reverse(regexp: &Rc<Regex>) -> Rc<Regex> {
+    match regexp.deref() {
+        Or(r1, r2) => Rc::new(Or(reverse(r1), reverse(r2))),
+        Concat(r1, r2) => Rc::new(Concat(reverse(r2), reverse(r1))),
+        Star(r1) => Rc::new(Star(reverse(r1))),
+        And(r1, r2) => Rc::new(And(reverse(r1), reverse(r2))),
+        Not(r1) => Rc::new(Not(reverse(r1))),
+        _ => regexp.clone()
+    }
+}
+
+impl Glushkov {
+    // Glushkov automaton of the reversed expression, built from this one: the positions and
+    // their labels stay the same, P and D are swapped and every pair of F is inverted
+    pub fn reversed(&self) -> Glushkov {
+        let P_set = self.D_set.clone();
+        let D_set = self.P_set.clone();
+        let F_set: HashSet<(u8, u8)> = self.F_set.iter().map(|(i, j)| (*j, *i)).collect();
+        let mut array: Vec<Vec<u8>> = vec![Vec::new(); self.no_of_states.into()];
+        for x in &P_set {
+            array[0].push(*x);
+        }
+        for x in &F_set {
+            array[x.0 as usize].push(x.1);
+        }
+        for x in array.iter_mut() {
+            x.sort();
+        }
+        Glushkov {
+            ast: reverse(&self.ast),
+            augmented: reverse(&self.augmented),
+            state_labels: self.state_labels.clone(),
+            no_of_states: self.no_of_states,
+            P_set,
+            D_set,
+            F_set,
+            nfa: array,
+            state_letter: self.state_letter.clone(),
+            nullable: self.nullable
+        }
+    }
+
+    fn accepting(&self, states: &[u8]) -> bool {
+        states.iter().any(|x| self.is_final(*x))
+    }
+
+    // End of the longest match starting at a position, scanning forward
+    pub fn longest_from(&self, s: &[u8], start: usize) -> Option<usize> {
+        let mut curr: Vec<u8> = vec![0];
+        let mut end = if self.nullable { Some(start) } else { None };
+        for (i, byte) in s.iter().enumerate().skip(start) {
+            curr = self.step(&curr, *byte);
+            if curr.is_empty() {
+                break;
+            }
+            if self.accepting(&curr) {
+                end = Some(i + 1);
+            }
+        }
+        end
+    }
+
+    // Where the forward scan can stop: a new match is started at every byte until a first match
+    // ends, then only the runs already started go on, until they all fail. Every match starting
+    // at or before the end of the first one, the leftmost one among them, ends by then
+    fn forward_bound(&self, s: &[u8]) -> Option<usize> {
+        let mut curr: Vec<u8> = Vec::new();
+        let mut seen = false;
+        for (i, byte) in s.iter().enumerate() {
+            if !seen {
+                curr.push(0);
+            }
+            curr = self.step(&curr, *byte);
+            seen = seen || self.accepting(&curr);
+            if seen && curr.is_empty() {
+                return Some(i + 1);
+            }
+        }
+        if seen { Some(s.len()) } else { None }
+    }
+
+    // Leftmost position a non-empty match starts at. The reversed automaton is run backward
+    // over the string, starting a new match at every byte, and accepts after reading the byte
+    // at i exactly when some match starts at i
+    fn leftmost_start(&self, s: &[u8]) -> Option<usize> {
+        let r = self.reversed();
+        let mut start = None;
+        let mut curr: Vec<u8> = Vec::new();
+        for i in (0..s.len()).rev() {
+            curr.push(0);
+            curr = r.step(&curr, s[i]);
+            if r.accepting(&curr) {
+                start = Some(i);
+            }
+        }
+        start
+    }
+
+    // Leftmost longest match as a range of byte offsets: an empty match at 0 for nullable
+    // expressions, otherwise a forward scan up to where the leftmost match has surely ended,
+    // a backward scan from there for its start and a forward scan from the start for its longest end
+    pub fn find(&self, s: &[u8]) -> Option<(usize, usize)> {
+        let start = if self.nullable {
+            0
+        } else {
+            let bound = self.forward_bound(s)?;
+            self.leftmost_start(&s[..bound]).unwrap()
+        };
+        Some((start, self.longest_from(s, start).unwrap()))
+    }
+
+    // Match starting the furthest to the right, and the longest from there. The reversed
+    // automaton run backward from the end of the string, starting a new match at every byte,
+    // finds the rightmost start
+    pub fn rfind(&self, s: &[u8]) -> Option<(usize, usize)> {
+        if self.nullable {
+            return Some((s.len(), s.len()));
+        }
+        let r = self.reversed();
+        let mut curr: Vec<u8> = Vec::new();
+        for i in (0..s.len()).rev() {
+            curr.push(0);
+            curr = r.step(&curr, s[i]);
+            if r.accepting(&curr) {
+                return Some((i, self.longest_from(s, i).unwrap()));
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::compile;
+    use crate::tests::all_strings;
+
+    // Leftmost longest match found by checking every (start, end) pair
+    fn brute_find(pattern: &str, s: &[u8]) -> Option<(usize, usize)> {
+        let g = compile(pattern).unwrap();
+        (0..=s.len()).find_map(|i| (i..=s.len()).rev().find(|j| g.accepts(&s[i..*j])).map(|j| (i, j)))
+    }
+
+    // Match starting the furthest to the right, and the longest from there
+    fn brute_rfind(pattern: &str, s: &[u8]) -> Option<(usize, usize)> {
+        let g = compile(pattern).unwrap();
+        (0..=s.len()).rev().find_map(|i| (i..=s.len()).rev().find(|j| g.accepts(&s[i..*j])).map(|j| (i, j)))
+    }
+
+    #[test]
+    fn reported_cases() {
+        let g = compile("[a-z]bcd|c").unwrap();
+        assert_eq!(g.find(b"xbcd"), Some((0, 4)));
+        let g = compile("(.c|(){1,})([a-c]\\x62|(a|a))").unwrap();
+        assert_eq!(g.find(b"bcbb"), Some((0, 4)));
+    }
+
+    #[test]
+    fn find_against_brute_force() {
+        let patterns = [
+            "[a-z]bcd|c", "(.c|(){1,})([a-c]\\x62|(a|a))", "a", "ab|b", "a*", "b+a", "(ab)*b", "a(b|cc)*",
+            "c|abc", "(a|b)*c", "[ab]b?", "bca|c", "(a|bc)(a|b)", "a{2,3}",
+        ];
+        let strings = all_strings(b"abc", 5);
+        for pattern in patterns {
+            let g = compile(pattern).unwrap();
+            for s in &strings {
+                assert_eq!(g.find(s), brute_find(pattern, s), "{} on {:?}", pattern, s);
+                assert_eq!(g.rfind(s), brute_rfind(pattern, s), "{} on {:?}", pattern, s);
+            }
+        }
+    }
+
+    // The scans stop soon after the leftmost match instead of going over the whole string
+    #[test]
+    fn scan_is_bounded_by_the_match() {
+        let mut s = b"xbcd".to_vec();
+        s.extend(vec![b'y'; 10000]);
+        let g = compile("[a-z]bcd|c").unwrap();
+        assert_eq!(g.forward_bound(&s), Some(5));
+        assert_eq!(g.find(&s), Some((0, 4)));
+        let g = compile("ab|e").unwrap();
+        assert_eq!(g.forward_bound(&s), None);
+        s[0] = b'a';
+        s[1] = b'b';
+        assert_eq!(g.forward_bound(&s), Some(3));
+        assert_eq!(g.find(&s), Some((0, 2)));
+        // Runs started before the first match ends are followed to their end
+        let g = compile("ab(c|d)*e|d").unwrap();
+        let mut s = b"abcdcdde".to_vec();
+        s.extend(vec![b'y'; 10000]);
+        assert_eq!(g.forward_bound(&s), Some(9));
+        assert_eq!(g.find(&s), Some((0, 8)));
+    }
+
+    #[test]
+    fn reversed_accepts_reversed_strings() {
+        let strings = all_strings(b"abc", 5);
+        for pattern in ["ab*c", "(a|bc)*", "a?b[bc]", "(ab|c)+a"] {
+            let g = compile(pattern).unwrap();
+            let r = g.reversed();
+            for s in &strings {
+                let mut t = s.clone();
+                t.reverse();
+                assert_eq!(g.accepts(s), r.accepts(&t), "{} on {:?}", pattern, s);
+            }
+        }
+    }
+}
@@ -0,0 +1,150 @@
+// Random generation of strings matched by a pattern, from its DFA
+
+use rand::Rng;
+
+use crate::classes::members;
+use crate::dfa::Dfa;
+
+impl Dfa {
+    // counts[r][s] : number of strings of length r accepted from state s, saturating at u128::MAX
+    pub fn path_counts(&self, length: usize) -> Vec<Vec<u128>> {
+        let n = self.no_of_states();
+        let sizes: Vec<u128> = members(&self.classes).iter().map(|x| x.len() as u128).collect();
+        let mut counts: Vec<Vec<u128>> = vec![self.finals.iter().map(|x| *x as u128).collect()];
+        for r in 1..=length {
+            let mut row: Vec<u128> = vec![0; n];
+            for (s, x) in row.iter_mut().enumerate() {
+                for (c, t) in self.trans[s].iter().enumerate() {
+                    *x = x.saturating_add(counts[r - 1][*t].saturating_mul(sizes[c]));
+                }
+            }
+            counts.push(row);
+        }
+        counts
+    }
+
+    // Draws a string of the given length uniformly among the accepted ones, walking from state 0
+    // and picking each byte with probability proportional to the number of ways to finish from
+    // the state it leads to. Exactly uniform as long as the counts fit in a u128. None when no
+    // string has that length
+    pub fn sample<R: Rng>(&self, length: usize, rng: &mut R) -> Option<Vec<u8>> {
+        let counts = self.path_counts(length);
+        if counts[length][0] == 0 {
+            return None;
+        }
+        let classes = members(&self.classes);
+        let mut res: Vec<u8> = Vec::with_capacity(length);
+        let mut curr = 0;
+        for r in (1..=length).rev() {
+            // Every byte of a class leads to the same state, so the class is picked first
+            let mut pick = rng.gen_range(0..counts[r][curr]);
+            for (bytes, next) in classes.iter().zip(&self.trans[curr]) {
+                let c = counts[r - 1][*next];
+                let total = c.saturating_mul(bytes.len() as u128);
+                if pick < total {
+                    res.push(bytes[(pick / c) as usize]);
+                    curr = *next;
+                    break;
+                }
+                pick -= total;
+            }
+        }
+        Some(res)
+    }
+
+    // States from which some accepting state can be reached
+    pub fn live_states(&self) -> Vec<bool> {
+        let mut live: Vec<bool> = self.finals.clone();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for s in 0..self.no_of_states() {
+                if !live[s] && self.trans[s].iter().any(|t| live[*t]) {
+                    live[s] = true;
+                    changed = true;
+                }
+            }
+        }
+        live
+    }
+
+    // Cheaper sampler for any length: a random walk through live states only, stopping in an
+    // accepting state with probability stop, or when max_len bytes have been read. Not uniform;
+    // None when the pattern matches nothing or the walk got no accepting state within max_len
+    pub fn random_walk<R: Rng>(&self, stop: f64, max_len: usize, rng: &mut R) -> Option<Vec<u8>> {
+        let live = self.live_states();
+        if !live[0] {
+            return None;
+        }
+        let mut res: Vec<u8> = Vec::new();
+        let mut curr = 0;
+        loop {
+            if self.finals[curr] && (res.len() == max_len || rng.gen_bool(stop)) {
+                return Some(res);
+            }
+            if res.len() == max_len {
+                return None;
+            }
+            let choices: Vec<u8> = (0..=255u8).filter(|b| live[self.next(curr, *b)]).collect();
+            // Only an accepting state can have no live successor
+            if choices.is_empty() {
+                return Some(res);
+            }
+            let byte = choices[rng.gen_range(0..choices.len())];
+            res.push(byte);
+            curr = self.next(curr, byte);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use crate::dfa;
+
+    #[test]
+    fn samples_are_accepted() {
+        let mut rng = StdRng::seed_from_u64(7);
+        for pattern in ["(a|b)*abb", "[a-z]+x[0-9]+", "(ab|c)*&~(.*cc.*)", ".a."] {
+            let d = dfa::compile(pattern).unwrap().minimise();
+            for length in 0..8 {
+                match d.sample(length, &mut rng) {
+                    Some(s) => {
+                        assert_eq!(s.len(), length, "{}", pattern);
+                        assert!(d.accepts(&s), "{} gave {:?}", pattern, s);
+                    },
+                    None => assert_eq!(d.count(length), 0, "{} at {}", pattern, length)
+                }
+            }
+            for _ in 0..20 {
+                if let Some(s) = d.random_walk(0.3, 10, &mut rng) {
+                    assert!(s.len() <= 10 && d.accepts(&s), "{} gave {:?}", pattern, s);
+                }
+            }
+        }
+    }
+
+    // Both strings of length 2 come out about as often, although the walk through a takes
+    // one branch out of two and the walk through b the other
+    #[test]
+    fn uniform() {
+        let mut rng = StdRng::seed_from_u64(11);
+        let d = dfa::compile("(a|bb)*").unwrap();
+        let n = 4000;
+        let aa = (0..n).filter(|_| d.sample(2, &mut rng).unwrap() == b"aa").count();
+        assert!((1700..2300).contains(&aa), "{}", aa);
+        let d = dfa::compile("a[a-z]|bc").unwrap();
+        let bc = (0..n).filter(|_| d.sample(2, &mut rng).unwrap() == b"bc").count();
+        assert!((80..230).contains(&bc), "{}", bc);
+    }
+
+    #[test]
+    fn nothing_to_sample() {
+        let mut rng = StdRng::seed_from_u64(0);
+        assert_eq!(dfa::compile("a&b").unwrap().sample(1, &mut rng), None);
+        assert_eq!(dfa::compile("a&b").unwrap().random_walk(0.5, 5, &mut rng), None);
+        assert_eq!(dfa::compile("ab").unwrap().sample(3, &mut rng), None);
+    }
+}
@@ -0,0 +1,250 @@
+// Algebraic simplification of the AST, run before augment to cut down the number of positions
+
+use std::ops::Deref;
+use std::rc::Rc;
+
+use crate::findLambda;
+use crate::Regex;
+use crate::Regex::{Empty, Eps, Letter, CharClass, Or, Concat, Star, And, Not};
+
+// Simplifies an AST bottom up through the smart constructors below
+pub fn simplify(regexp: &Rc<Regex>) -> Rc<Regex> {
+    match regexp.deref() {
+        Or(r1, r2) => mkOr(&simplify(r1), &simplify(r2)),
+        Concat(r1, r2) => mkConcat(&simplify(r1), &simplify(r2)),
+        Star(r1) => mkStar(&simplify(r1)),
+        And(r1, r2) => mkAnd(&simplify(r1), &simplify(r2)),
+        Not(r1) => mkNot(&simplify(r1)),
+        CharClass(charvec) => mkClass(charvec.clone()),
+        _ => regexp.clone()
+    }
+}
+
+// Class with no byte is the empty language, class with one byte is a letter
+pub fn mkClass(charvec: Vec<bool>) -> Rc<Regex> {
+    let count = charvec.iter().filter(|x| **x).count();
+    if count == 0 {
+        Rc::new(Empty())
+    } else if count == 1 {
+        Rc::new(Letter(charvec.iter().position(|x| *x).unwrap() as u8))
+    } else {
+        Rc::new(CharClass(charvec))
+    }
+}
+
+// ∅·r = r·∅ = ∅, ε·r = r·ε = r, and concatenations are kept nested to the right
+pub fn mkConcat(r1: &Rc<Regex>, r2: &Rc<Regex>) -> Rc<Regex> {
+    match (r1.deref(), r2.deref()) {
+        (Empty(), _) | (_, Empty()) => Rc::new(Empty()),
+        (Eps(), _) => r2.clone(),
+        (_, Eps()) => r1.clone(),
+        (Concat(a, b), _) => mkConcat(a, &mkConcat(b, r2)),
+        _ => Rc::new(Concat(r1.clone(), r2.clone()))
+    }
+}
+
+// (r*)* = r*, ∅* = ε* = ε, (r|ε|s)* = (r|s)* whatever the number of alternatives
+pub fn mkStar(r1: &Rc<Regex>) -> Rc<Regex> {
+    match r1.deref() {
+        Star(_) => r1.clone(),
+        Empty() | Eps() => Rc::new(Eps()),
+        Or(_, _) => {
+            let mut alts: Vec<Rc<Regex>> = Vec::new();
+            alternatives(r1, &mut alts);
+            let len = alts.len();
+            alts.retain(|r| !matches!(r.deref(), Eps()));
+            if alts.len() == len {
+                return Rc::new(Star(r1.clone()));
+            }
+            mkStar(&alts.iter().fold(Rc::new(Empty()), |res, r| mkOr(&res, r)))
+        },
+        _ => Rc::new(Star(r1.clone()))
+    }
+}
+
+// ∅&r = r&∅ = ∅, r&r = r
+pub fn mkAnd(r1: &Rc<Regex>, r2: &Rc<Regex>) -> Rc<Regex> {
+    match (r1.deref(), r2.deref()) {
+        (Empty(), _) | (_, Empty()) => Rc::new(Empty()),
+        _ if r1 == r2 => r1.clone(),
+        _ => Rc::new(And(r1.clone(), r2.clone()))
+    }
+}
+
+// ~~r = r
+pub fn mkNot(r1: &Rc<Regex>) -> Rc<Regex> {
+    match r1.deref() {
+        Not(a) => a.clone(),
+        _ => Rc::new(Not(r1.clone()))
+    }
+}
+
+// Collects the alternatives of nested Or nodes
+fn alternatives(regexp: &Rc<Regex>, alts: &mut Vec<Rc<Regex>>) {
+    match regexp.deref() {
+        Or(r1, r2) => {
+            alternatives(r1, alts);
+            alternatives(r2, alts);
+        },
+        _ => alts.push(regexp.clone())
+    }
+}
+
+// First factor of a concatenation and the rest of it
+fn split_head(regexp: &Rc<Regex>) -> (Rc<Regex>, Rc<Regex>) {
+    match regexp.deref() {
+        Concat(r1, r2) => (r1.clone(), r2.clone()),
+        _ => (regexp.clone(), Rc::new(Eps()))
+    }
+}
+
+fn is_nullable(regexp: &Rc<Regex>) -> bool {
+    matches!(findLambda(regexp).deref(), Eps())
+}
+
+// ∅|r = r, r|r = r, ε|r = r for nullable r, alternatives starting with the same factor
+// are factored (ab|ac = a(b|c)) and single letter or class alternatives are merged into one class
+pub fn mkOr(r1: &Rc<Regex>, r2: &Rc<Regex>) -> Rc<Regex> {
+    let mut all: Vec<Rc<Regex>> = Vec::new();
+    alternatives(r1, &mut all);
+    alternatives(r2, &mut all);
+
+    let mut alts: Vec<Rc<Regex>> = Vec::new();
+    for r in all {
+        if !matches!(r.deref(), Empty()) && !alts.contains(&r) {
+            alts.push(r);
+        }
+    }
+
+    // Factor common prefixes, keeping the groups in order of first appearance
+    let mut groups: Vec<(Rc<Regex>, Vec<Rc<Regex>>)> = Vec::new();
+    for r in &alts {
+        let (head, tail) = split_head(r);
+        match groups.iter_mut().find(|(h, _)| *h == head) {
+            Some((_, tails)) => tails.push(tail),
+            None => groups.push((head, vec![tail]))
+        }
+    }
+    let mut factored: Vec<Rc<Regex>> = Vec::new();
+    for (head, tails) in groups {
+        if tails.len() == 1 {
+            factored.push(mkConcat(&head, &tails[0]));
+        } else {
+            let mut rest = tails[0].clone();
+            for t in &tails[1..] {
+                rest = mkOr(&rest, t);
+            }
+            factored.push(mkConcat(&head, &rest));
+        }
+    }
+
+    // Merge letters and classes into the first of them
+    let mut merged: Vec<Rc<Regex>> = Vec::new();
+    let mut class: Option<(usize, Vec<bool>)> = None;
+    for r in factored {
+        let charvec = match r.deref() {
+            Letter(a) => {
+                let mut v = vec![false; 256];
+                v[*a as usize] = true;
+                v
+            },
+            CharClass(v) => v.clone(),
+            _ => {
+                merged.push(r);
+                continue;
+            }
+        };
+        match class.as_mut() {
+            Some((_, v)) => {
+                for (x, y) in v.iter_mut().zip(charvec.iter()) {
+                    *x |= *y;
+                }
+            },
+            None => {
+                class = Some((merged.len(), charvec));
+                merged.push(Rc::new(Eps()));
+            }
+        }
+    }
+    if let Some((idx, v)) = class {
+        merged[idx] = mkClass(v);
+    }
+
+    // ε is only needed when no other alternative is nullable, and goes last so it prints as ?
+    let has_eps = merged.iter().any(|r| matches!(r.deref(), Eps()));
+    merged.retain(|r| !matches!(r.deref(), Eps()));
+    if has_eps && !merged.iter().any(is_nullable) {
+        merged.push(Rc::new(Eps()));
+    }
+
+    match merged.pop() {
+        None => if has_eps { Rc::new(Eps()) } else { Rc::new(Empty()) },
+        Some(last) => {
+            let mut res = last;
+            while let Some(r) = merged.pop() {
+                res = Rc::new(Or(r, res));
+            }
+            res
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::all_strings;
+    use crate::{compile, compile_unsimplified, dfa, parse};
+
+    fn simplified(pattern: &str) -> String {
+        simplify(&parse(pattern).unwrap().0).to_string()
+    }
+
+    #[test]
+    fn identities() {
+        assert_eq!(simplified("()a()"), "a");
+        assert_eq!(simplified("(a*)*"), "a*");
+        assert_eq!(simplified("(a?)*"), "a*");
+        assert_eq!(simplified("(a|())*"), "a*");
+        assert_eq!(simplified("(a|()|b*)*"), "(a|b*)*");
+        assert_eq!(simplified("(ab|()|ba)*"), "(ab|ba)*");
+        assert_eq!(simplified("(()|a|b)*"), "[a-b]*");
+        assert_eq!(simplified("[a]"), "a");
+        assert_eq!(simplified("a|[bc]|d"), "[a-d]");
+        assert_eq!(simplified("ab|ac"), "a[b-c]");
+        assert_eq!(simplified("a|a"), "a");
+        assert_eq!(simplified("a*|()"), "a*");
+        assert_eq!(simplified("~~a"), "a");
+        assert_eq!(simplified("a&a"), "a");
+    }
+
+    // The simplified and the unsimplified automata accept the same strings, the simplified one with no more positions
+    #[test]
+    fn same_language() {
+        let patterns = [
+            "a?b?c?", "(a|b)*abb", "(ab|ac)*", "a{2,4}b{,2}", "(a+)*", "(()|a)(b|())", "(a|b|[bc])+",
+            "ab|ac|b", "(a*b*)*c?", "((a|())*)*", "(a|()|bc)*", "(()|ab|b|())*c", "a{3}|a{2,}", "(ab|a)(ba|b)",
+        ];
+        let strings = all_strings(b"abc", 6);
+        for pattern in patterns {
+            let g = compile_unsimplified(pattern).unwrap();
+            let h = compile(pattern).unwrap();
+            assert!(h.no_of_states <= g.no_of_states, "{}", pattern);
+            for s in &strings {
+                assert_eq!(g.accepts(s), h.accepts(s), "{} on {:?}", pattern, s);
+            }
+        }
+    }
+
+    #[test]
+    fn same_language_extended() {
+        let strings = all_strings(b"abc", 5);
+        for pattern in ["~~(ab)*", "(a|a)&(a*)*", "~(()a)|b&b", "(a*&~())*"] {
+            let (x, _) = parse(pattern).unwrap();
+            let d1 = dfa::fromRegex(&x);
+            let d2 = dfa::fromRegex(&simplify(&x));
+            for s in &strings {
+                assert_eq!(d1.accepts(s), d2.accepts(s), "{} on {:?}", pattern, s);
+            }
+        }
+    }
+}
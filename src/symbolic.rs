@@ -0,0 +1,323 @@
+// Symbolic automata: transitions labelled by predicates, sorted lists of disjoint intervals of
+// symbols, instead of one transition per byte or byte class. Determinisation, intersection and
+// minimisation only look at the minterms of the predicates, the satisfiable conjunctions of
+// each predicate or its negation, so their cost does not grow with the size of the alphabet
+
+use std::collections::{HashMap, VecDeque};
+
+use crate::{classRanges, Glushkov};
+
+// Largest symbol; bytes for now, the algorithms below work the same for Unicode code points
+pub const MAX_SYMBOL: u32 = 255;
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Pred(pub Vec<(u32, u32)>);
+
+impl Pred {
+    pub fn full() -> Pred {
+        Pred(vec![(0, MAX_SYMBOL)])
+    }
+
+    pub fn from_class(charvec: &[bool]) -> Pred {
+        Pred(classRanges(charvec).into_iter().map(|(a, b)| (a as u32, b as u32)).collect())
+    }
+
+    // The bytes satisfying the predicate
+    pub fn to_class(&self) -> Vec<bool> {
+        (0..256).map(|x| self.contains(x)).collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn contains(&self, symbol: u32) -> bool {
+        self.0.iter().any(|(a, b)| *a <= symbol && symbol <= *b)
+    }
+
+    pub fn and(&self, other: &Pred) -> Pred {
+        let mut res: Vec<(u32, u32)> = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        while i < self.0.len() && j < other.0.len() {
+            let (a1, b1) = self.0[i];
+            let (a2, b2) = other.0[j];
+            if a1.max(a2) <= b1.min(b2) {
+                res.push((a1.max(a2), b1.min(b2)));
+            }
+            if b1 < b2 { i += 1 } else { j += 1 }
+        }
+        Pred(res)
+    }
+
+    pub fn not(&self) -> Pred {
+        let mut res: Vec<(u32, u32)> = Vec::new();
+        let mut next = 0;
+        for (a, b) in &self.0 {
+            if *a > next {
+                res.push((next, a - 1));
+            }
+            next = b + 1;
+        }
+        if next <= MAX_SYMBOL {
+            res.push((next, MAX_SYMBOL));
+        }
+        Pred(res)
+    }
+
+    pub fn or(&self, other: &Pred) -> Pred {
+        self.not().and(&other.not()).not()
+    }
+}
+
+// Minterms of a set of predicates: the non-empty predicates splitting the alphabet so that each
+// satisfies or contradicts every given predicate as a whole
+pub fn minterms<'a, I: IntoIterator<Item = &'a Pred>>(preds: I) -> Vec<Pred> {
+    let mut res: Vec<Pred> = vec![Pred::full()];
+    for p in preds {
+        let q = p.not();
+        res = res.iter().flat_map(|m| [m.and(p), m.and(&q)]).filter(|m| !m.is_empty()).collect();
+    }
+    res
+}
+
+// Symbolic automaton, state 0 being initial, deterministic when the predicates out of every
+// state are disjoint
+pub struct Sfa {
+    pub finals: Vec<bool>,
+    pub edges: Vec<Vec<(Pred, usize)>>
+}
+
+// Adds an edge, joining its predicate to the edge already going to the same state if any
+fn addEdge(out: &mut Vec<(Pred, usize)>, p: Pred, t: usize) {
+    match out.iter_mut().find(|(_, target)| *target == t) {
+        Some((q, _)) => *q = q.or(&p),
+        None => out.push((p, t))
+    }
+}
+
+impl Glushkov {
+    // Each position labelled by the predicate of its class, on the transitions entering it
+    pub fn to_sfa(&self) -> Sfa {
+        Sfa {
+            finals: (0..self.no_of_states).map(|x| self.is_final(x)).collect(),
+            edges: self.nfa.iter().map(|next| {
+                next.iter().map(|j| (Pred::from_class(&self.state_letter[(j-1) as usize]), *j as usize)).collect()
+            }).collect()
+        }
+    }
+}
+
+// Breadth-first construction of a symbolic automaton over hashable states from state 0
+fn build<K, S, A>(start: K, step: S, accept: A) -> Sfa
+where
+    K: Clone + Eq + std::hash::Hash,
+    S: Fn(&K) -> Vec<(Pred, K)>,
+    A: Fn(&K) -> bool
+{
+    let mut ids: HashMap<K, usize> = HashMap::new();
+    let mut queue: VecDeque<K> = VecDeque::new();
+    ids.insert(start.clone(), 0);
+    queue.push_back(start);
+    let mut finals: Vec<bool> = Vec::new();
+    let mut edges: Vec<Vec<(Pred, usize)>> = Vec::new();
+    while let Some(curr) = queue.pop_front() {
+        finals.push(accept(&curr));
+        let mut out: Vec<(Pred, usize)> = Vec::new();
+        for (p, next) in step(&curr) {
+            let id = match ids.get(&next) {
+                Some(id) => *id,
+                None => {
+                    let id = ids.len();
+                    ids.insert(next.clone(), id);
+                    queue.push_back(next);
+                    id
+                }
+            };
+            addEdge(&mut out, p, id);
+        }
+        edges.push(out);
+    }
+    Sfa { finals, edges }
+}
+
+// Product automaton, accepting the strings both accept
+pub fn intersection(a: &Sfa, b: &Sfa) -> Sfa {
+    build((0usize, 0usize), |(s, t)| {
+        let mut res: Vec<(Pred, (usize, usize))> = Vec::new();
+        for (p, s1) in &a.edges[*s] {
+            for (q, t1) in &b.edges[*t] {
+                let r = p.and(q);
+                if !r.is_empty() {
+                    res.push((r, (*s1, *t1)));
+                }
+            }
+        }
+        res
+    }, |(s, t)| a.finals[*s] && b.finals[*t])
+}
+
+impl Sfa {
+    pub fn no_of_states(&self) -> usize {
+        self.finals.len()
+    }
+
+    pub fn no_of_transitions(&self) -> usize {
+        self.edges.iter().map(|x| x.len()).sum()
+    }
+
+    // Runs the automaton on sets of states
+    pub fn accepts(&self, s: &[u8]) -> bool {
+        let mut curr: Vec<usize> = vec![0];
+        for byte in s {
+            let mut next: Vec<usize> = Vec::new();
+            for x in &curr {
+                for (p, t) in &self.edges[*x] {
+                    if p.contains(*byte as u32) && !next.contains(t) {
+                        next.push(*t);
+                    }
+                }
+            }
+            curr = next;
+        }
+        curr.iter().any(|x| self.finals[*x])
+    }
+
+    // Subset construction: out of a set of states, one transition per minterm of their
+    // predicates, to the states whose predicate the minterm satisfies. The result is complete,
+    // the empty set being the dead state
+    pub fn determinise(&self) -> Sfa {
+        build(vec![0usize], |set| {
+            let out: Vec<&(Pred, usize)> = set.iter().flat_map(|s| &self.edges[*s]).collect();
+            minterms(out.iter().map(|(p, _)| p)).into_iter().map(|m| {
+                let a = m.0[0].0;
+                let mut next: Vec<usize> = out.iter().filter(|(p, _)| p.contains(a)).map(|(_, t)| *t).collect();
+                next.sort();
+                next.dedup();
+                (m, next)
+            }).collect()
+        }, |set| set.iter().any(|s| self.finals[*s]))
+    }
+
+    // Moore's partition refinement on a complete deterministic automaton, successors being
+    // compared on every minterm of all the predicates
+    pub fn minimise(&self) -> Sfa {
+        let n = self.no_of_states();
+        let terms = minterms(self.edges.iter().flatten().map(|(p, _)| p));
+        let target = |s: usize, m: &Pred| self.edges[s].iter().find(|(p, _)| p.contains(m.0[0].0)).unwrap().1;
+        let mut block: Vec<usize> = self.finals.iter().map(|x| if *x { 1 } else { 0 }).collect();
+        let mut no_of_blocks = 0;
+        loop {
+            let mut ids: HashMap<Vec<usize>, usize> = HashMap::new();
+            let mut next_block: Vec<usize> = vec![0; n];
+            for s in 0..n {
+                let mut signature: Vec<usize> = vec![block[s]];
+                signature.extend(terms.iter().map(|m| block[target(s, m)]));
+                let len = ids.len();
+                next_block[s] = *ids.entry(signature).or_insert(len);
+            }
+            block = next_block;
+            if ids.len() == no_of_blocks {
+                break;
+            }
+            no_of_blocks = ids.len();
+        }
+        // One representative state per block, numbered so that the block of state 0 comes first
+        let mut rep: Vec<usize> = vec![usize::MAX; no_of_blocks];
+        for s in (0..n).rev() {
+            rep[block[s]] = s;
+        }
+        build(block[0], |b| {
+            self.edges[rep[*b]].iter().map(|(p, t)| (p.clone(), block[*t])).collect()
+        }, |b| self.finals[rep[*b]])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::all_strings;
+    use crate::{compile, dfa};
+
+    const PATTERNS: [&str; 8] = [
+        "a[b-c]*", "(a|b)*abb", "[^a]b|[a-c]", "(ab|[a-z])*0", "a?b?c?", "[ab]{2,3}", "(a*b*)*c", ".a|[0-9]+",
+    ];
+
+    fn preds() -> Vec<Pred> {
+        vec![
+            Pred(vec![(0, 10), (97, 99)]),
+            Pred(vec![(5, 98)]),
+            Pred(vec![(99, 255)]),
+            Pred(vec![]),
+            Pred::full(),
+        ]
+    }
+
+    #[test]
+    fn boolean_operations() {
+        for p in preds() {
+            for q in preds() {
+                for x in 0..=MAX_SYMBOL {
+                    assert_eq!(p.and(&q).contains(x), p.contains(x) && q.contains(x), "{:?} {:?} {}", p, q, x);
+                    assert_eq!(p.or(&q).contains(x), p.contains(x) || q.contains(x), "{:?} {:?} {}", p, q, x);
+                }
+            }
+            for x in 0..=MAX_SYMBOL {
+                assert_eq!(p.not().contains(x), !p.contains(x), "{:?} {}", p, x);
+            }
+        }
+    }
+
+    // Minterms are non-empty, disjoint, cover the alphabet, and lie inside or outside every predicate
+    #[test]
+    fn minterms_split_the_alphabet() {
+        let ps = preds();
+        let terms = minterms(&ps);
+        assert!(terms.iter().all(|m| !m.is_empty()));
+        for x in 0..=MAX_SYMBOL {
+            assert_eq!(terms.iter().filter(|m| m.contains(x)).count(), 1, "{}", x);
+        }
+        for m in &terms {
+            for p in &ps {
+                assert!(m.and(p).is_empty() || m.and(&p.not()).is_empty(), "{:?} {:?}", m, p);
+            }
+        }
+    }
+
+    // The symbolic automaton and its determinised and minimised forms against the Glushkov
+    // automaton, the minimal one being as small as the minimal DFA
+    #[test]
+    fn agrees_with_glushkov() {
+        let strings = all_strings(b"ab0c", 5);
+        for pattern in PATTERNS {
+            let g = compile(pattern).unwrap();
+            let a = g.to_sfa();
+            let d = a.determinise();
+            let m = d.minimise();
+            for edges in &d.edges {
+                for x in 0..=MAX_SYMBOL {
+                    assert_eq!(edges.iter().filter(|(p, _)| p.contains(x)).count(), 1, "{} {}", pattern, x);
+                }
+            }
+            assert_eq!(m.no_of_states(), dfa::determinise(&g).minimise().no_of_states(), "{}", pattern);
+            for s in &strings {
+                assert_eq!(a.accepts(s), g.accepts(s), "{} on {:?}", pattern, s);
+                assert_eq!(d.accepts(s), g.accepts(s), "{} on {:?}", pattern, s);
+                assert_eq!(m.accepts(s), g.accepts(s), "{} on {:?}", pattern, s);
+            }
+        }
+    }
+
+    #[test]
+    fn intersection_agrees_with_dfa() {
+        let strings = all_strings(b"ab0c", 5);
+        for p1 in PATTERNS {
+            for p2 in ["(a|b)*", ".*b.*", "[a-c0]{2,4}"] {
+                let a = intersection(&compile(p1).unwrap().to_sfa(), &compile(p2).unwrap().to_sfa());
+                let d = dfa::compile(&format!("({})&({})", p1, p2)).unwrap();
+                for s in &strings {
+                    assert_eq!(a.accepts(s), d.accepts(s), "{} {} on {:?}", p1, p2, s);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,325 @@
+// Boolean trigram queries for a trigram index, in the style of Google Code Search: every
+// string the pattern matches inside a document satisfies the query, so the documents that do
+// not can be skipped
+
+use std::fmt;
+
+use crate::explain::show_byte;
+use crate::literals::{cross, Strings, MAX_CLASS, MAX_SET};
+use crate::Regex;
+use crate::Regex::{Empty, Eps, Letter, CharClass, Or, Concat, Star, And, Not};
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Query {
+    // Satisfied by every document
+    All,
+    // Satisfied by no document
+    None,
+    Trigram(Vec<u8>),
+    And(Vec<Query>),
+    Or(Vec<Query>)
+}
+
+// Conjunction with the trivial cases removed and nested conjunctions flattened
+fn qAnd(q1: Query, q2: Query) -> Query {
+    let mut items: Vec<Query> = Vec::new();
+    for q in [q1, q2] {
+        match q {
+            Query::None => return Query::None,
+            Query::All => {},
+            Query::And(x) => items.extend(x),
+            q => items.push(q)
+        }
+    }
+    items.sort();
+    items.dedup();
+    // x AND (x OR y) = x
+    let all = items.clone();
+    items.retain(|q| !matches!(q, Query::Or(x) if x.iter().any(|p| all.contains(p))));
+    match items.len() {
+        0 => Query::All,
+        1 => items.pop().unwrap(),
+        _ => Query::And(items)
+    }
+}
+
+// Disjunction with the trivial cases removed and nested disjunctions flattened
+fn qOr(q1: Query, q2: Query) -> Query {
+    let mut items: Vec<Query> = Vec::new();
+    for q in [q1, q2] {
+        match q {
+            Query::All => return Query::All,
+            Query::None => {},
+            Query::Or(x) => items.extend(x),
+            q => items.push(q)
+        }
+    }
+    items.sort();
+    items.dedup();
+    // x OR (x AND y) = x
+    let all = items.clone();
+    items.retain(|q| !matches!(q, Query::And(x) if x.iter().any(|p| all.contains(p))));
+    match items.len() {
+        0 => Query::None,
+        1 => items.pop().unwrap(),
+        _ => Query::Or(items)
+    }
+}
+
+// Query satisfied by text containing one of the strings: some string shorter than a trigram
+// tells nothing
+fn trigrams(set: &Strings) -> Query {
+    set.iter().fold(Query::None, |q, s| {
+        let t = s.windows(3).fold(Query::All, |q, w| qAnd(q, Query::Trigram(w.to_vec())));
+        qOr(q, t)
+    })
+}
+
+// What is known about the matches of a subexpression: whether it matches the empty string,
+// all its matches when they are few, otherwise sets of prefixes and suffixes of its matches,
+// and a query its matches satisfy
+struct Analysis {
+    emptyable: bool,
+    exact: Option<Strings>,
+    prefix: Strings,
+    suffix: Strings,
+    query: Query
+}
+
+fn nothingKnown(emptyable: bool) -> Analysis {
+    let mut empty = Strings::new();
+    empty.insert(Vec::new());
+    Analysis {
+        emptyable,
+        exact: None,
+        prefix: empty.clone(),
+        suffix: empty,
+        query: Query::All
+    }
+}
+
+fn exactly(set: Strings) -> Analysis {
+    Analysis {
+        emptyable: set.contains(&Vec::new()),
+        prefix: Strings::new(),
+        suffix: Strings::new(),
+        exact: Some(set),
+        query: Query::All
+    }
+}
+
+// Cuts every string of a set down to its first (or last) n bytes
+fn trim(set: &Strings, n: usize, prefix: bool) -> Strings {
+    set.iter().map(|s| {
+        if s.len() <= n {
+            s.clone()
+        } else if prefix {
+            s[..n].to_vec()
+        } else {
+            s[s.len()-n..].to_vec()
+        }
+    }).collect()
+}
+
+impl Analysis {
+    // Keeps the sets small: a large exact set goes into the query and becomes the prefixes and
+    // suffixes, which go into the query in turn and are cut to the two bytes that may still
+    // start or end a trigram across the next concatenation
+    fn save(mut self) -> Analysis {
+        if let Some(exact) = &self.exact {
+            if exact.len() <= MAX_SET {
+                return self;
+            }
+            self.query = qAnd(self.query, trigrams(exact));
+            self.prefix = exact.clone();
+            self.suffix = exact.clone();
+            self.exact = None;
+        }
+        if self.prefix.iter().any(|s| s.len() > 2) {
+            self.query = qAnd(self.query, trigrams(&self.prefix));
+            self.prefix = trim(&self.prefix, 2, true);
+        }
+        if self.suffix.iter().any(|s| s.len() > 2) {
+            self.query = qAnd(self.query, trigrams(&self.suffix));
+            self.suffix = trim(&self.suffix, 2, false);
+        }
+        for n in [1, 0] {
+            if self.prefix.len() > MAX_SET {
+                self.prefix = trim(&self.prefix, n, true);
+            }
+            if self.suffix.len() > MAX_SET {
+                self.suffix = trim(&self.suffix, n, false);
+            }
+        }
+        self
+    }
+
+    fn prefixes(&self) -> &Strings {
+        self.exact.as_ref().unwrap_or(&self.prefix)
+    }
+
+    fn suffixes(&self) -> &Strings {
+        self.exact.as_ref().unwrap_or(&self.suffix)
+    }
+}
+
+fn analyse(regexp: &Regex) -> Analysis {
+    match regexp {
+        Empty() => Analysis { query: Query::None, ..exactly(Strings::new()) },
+        Eps() => exactly([Vec::new()].into_iter().collect()),
+        Letter(a) => exactly([vec![*a]].into_iter().collect()),
+        CharClass(charvec) => {
+            if charvec.iter().filter(|x| **x).count() <= MAX_CLASS {
+                exactly((0..256).filter(|x| charvec[*x]).map(|x| vec![x as u8]).collect())
+            } else {
+                nothingKnown(false)
+            }
+        },
+        Or(r1, r2) => {
+            let x = analyse(r1);
+            let y = analyse(r2);
+            match (&x.exact, &y.exact) {
+                (Some(a), Some(b)) => Analysis {
+                    query: qOr(x.query.clone(), y.query.clone()),
+                    ..exactly(a.union(b).cloned().collect())
+                },
+                _ => Analysis {
+                    emptyable: x.emptyable || y.emptyable,
+                    exact: None,
+                    prefix: x.prefixes().union(y.prefixes()).cloned().collect(),
+                    suffix: x.suffixes().union(y.suffixes()).cloned().collect(),
+                    query: qOr(x.query, y.query)
+                }
+            }.save()
+        },
+        Concat(r1, r2) => {
+            let x = analyse(r1);
+            let y = analyse(r2);
+            // Trigrams across the boundary between the operands
+            let across = match (&x.exact, &y.exact) {
+                (Some(_), Some(_)) => Query::All,
+                _ => trigrams(&cross(x.suffixes(), y.prefixes()))
+            };
+            let query = qAnd(qAnd(x.query.clone(), y.query.clone()), across);
+            match (&x.exact, &y.exact) {
+                (Some(a), Some(b)) => Analysis { query, ..exactly(cross(a, b)) },
+                _ => {
+                    let prefix = match &x.exact {
+                        Some(a) => cross(a, y.prefixes()),
+                        None if x.emptyable => x.prefix.union(y.prefixes()).cloned().collect(),
+                        None => x.prefix.clone()
+                    };
+                    let suffix = match &y.exact {
+                        Some(b) => cross(x.suffixes(), b),
+                        None if y.emptyable => y.suffix.union(x.suffixes()).cloned().collect(),
+                        None => y.suffix.clone()
+                    };
+                    Analysis {
+                        emptyable: x.emptyable && y.emptyable,
+                        exact: None,
+                        prefix,
+                        suffix,
+                        query
+                    }
+                }
+            }.save()
+        },
+        Star(_) => nothingKnown(true),
+        And(r1, r2) => {
+            let x = analyse(r1);
+            let y = analyse(r2);
+            // The matches satisfy both queries, and the sets of either operand
+            let exact = |a: Analysis| qAnd(a.query.clone(), match &a.exact {
+                Some(e) => trigrams(e),
+                None => qAnd(trigrams(&a.prefix), trigrams(&a.suffix))
+            });
+            Analysis {
+                query: qAnd(exact(x), exact(y)),
+                ..nothingKnown(false)
+            }
+        },
+        Not(_) => nothingKnown(true)
+    }
+}
+
+// Query that the text of every document containing a match of the expression satisfies
+pub fn trigram_query(regexp: &Regex) -> Query {
+    let a = analyse(regexp);
+    match &a.exact {
+        Some(e) => qAnd(a.query, trigrams(e)),
+        None => qAnd(a.query, qAnd(trigrams(&a.prefix), trigrams(&a.suffix)))
+    }
+}
+
+impl fmt::Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let join = |f: &mut fmt::Formatter, items: &[Query], op: &str| -> fmt::Result {
+            for (i, q) in items.iter().enumerate() {
+                if i > 0 {
+                    write!(f, " {} ", op)?;
+                }
+                match q {
+                    Query::And(_) | Query::Or(_) => write!(f, "({})", q)?,
+                    _ => write!(f, "{}", q)?
+                }
+            }
+            Ok(())
+        };
+        match self {
+            Query::All => write!(f, "ALL"),
+            Query::None => write!(f, "NONE"),
+            Query::Trigram(t) => write!(f, "\"{}\"", t.iter().map(|x| show_byte(*x)).collect::<String>()),
+            Query::And(items) => join(f, items, "AND"),
+            Query::Or(items) => join(f, items, "OR")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dfa;
+    use crate::parse;
+    use crate::tests::all_strings;
+
+    fn query(pattern: &str) -> Query {
+        trigram_query(&parse(pattern).unwrap().0)
+    }
+
+    fn satisfies(q: &Query, text: &[u8]) -> bool {
+        match q {
+            Query::All => true,
+            Query::None => false,
+            Query::Trigram(t) => text.windows(3).any(|w| w == t.as_slice()),
+            Query::And(items) => items.iter().all(|q| satisfies(q, text)),
+            Query::Or(items) => items.iter().any(|q| satisfies(q, text))
+        }
+    }
+
+    #[test]
+    fn known_queries() {
+        assert_eq!(query("abcd").to_string(), "\"abc\" AND \"bcd\"");
+        assert_eq!(query("abc|bcd").to_string(), "\"abc\" OR \"bcd\"");
+        assert_eq!(query("ab*").to_string(), "ALL");
+        assert_eq!(query("abc&.*bcd").to_string(), "\"abc\" AND \"bcd\"");
+    }
+
+    // Every text containing a match must satisfy the query
+    #[test]
+    fn matched_texts_satisfy_the_query() {
+        let patterns = [
+            "abc", "abc|cab", "a(b|c)*a", "(ab|ac)cb*", "ab(c|a)ab", "[abc]{2}bc", "(abc)*", "abb&.*b",
+            "~(ab)", "(a|b)*abb", "c?(ba|bb)ca", "(a|())bc(c|())", "ab[ab]+&(.*c.*)", "(aab|abb)(a|c)",
+        ];
+        let texts = all_strings(b"abc", 6);
+        for pattern in patterns {
+            let d = dfa::compile(&format!(".*({}).*", pattern)).unwrap();
+            let q = query(pattern);
+            for s in &texts {
+                if d.accepts(s) {
+                    assert!(satisfies(&q, s), "{} {} on {:?}", pattern, q, s);
+                }
+            }
+        }
+    }
+}